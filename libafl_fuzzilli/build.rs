@@ -0,0 +1,13 @@
+fn main() {
+    // The gRPC server is generated from proto/bridge.proto, with a vendored protoc so that
+    // building with `--features grpc` needs nothing installed
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/bridge.proto");
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this platform"));
+        tonic_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/bridge.proto"], &["proto"])
+            .expect("failed to compile proto/bridge.proto");
+    }
+}
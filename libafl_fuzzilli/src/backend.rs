@@ -0,0 +1,120 @@
+use core::cell::RefCell;
+
+use libafl::{
+    corpus::{CachedOnDiskCorpus, Corpus, CorpusId, InMemoryCorpus, OnDiskCorpus, Testcase},
+    inputs::{BytesInput, UsesInput},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+// Which corpus implementation holds the main corpus. The solutions corpus always stays on disk.
+#[derive(uniffi::Enum, Debug, Clone, PartialEq, Eq)]
+pub enum FzilCorpusBackend {
+    // Nothing is written to disk, fastest for embedded use. The corpus is gone on exit unless
+    // kept with save_state, which then includes the inputs themselves.
+    InMemory,
+    // Inputs live in ./pcorpus and are loaded on demand (the default)
+    OnDisk,
+    // Like OnDisk, but keeps up to `cache_size` loaded inputs in memory
+    CachedOnDisk { cache_size: u64 },
+}
+
+// The main corpus behind whichever backend was picked at construction
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) enum MainCorpus {
+    InMemory(InMemoryCorpus<BytesInput>),
+    OnDisk(OnDiskCorpus<BytesInput>),
+    CachedOnDisk(CachedOnDiskCorpus<BytesInput>),
+}
+
+macro_rules! delegate {
+    ($self:expr, $corpus:ident => $body:expr) => {
+        match $self {
+            MainCorpus::InMemory($corpus) => $body,
+            MainCorpus::OnDisk($corpus) => $body,
+            MainCorpus::CachedOnDisk($corpus) => $body,
+        }
+    };
+}
+
+impl UsesInput for MainCorpus {
+    type Input = BytesInput;
+}
+
+impl Corpus for MainCorpus {
+    fn count(&self) -> usize {
+        delegate!(self, corpus => corpus.count())
+    }
+
+    fn count_disabled(&self) -> usize {
+        delegate!(self, corpus => corpus.count_disabled())
+    }
+
+    fn count_all(&self) -> usize {
+        delegate!(self, corpus => corpus.count_all())
+    }
+
+    fn add(&mut self, testcase: Testcase<BytesInput>) -> Result<CorpusId, Error> {
+        delegate!(self, corpus => corpus.add(testcase))
+    }
+
+    fn add_disabled(&mut self, testcase: Testcase<BytesInput>) -> Result<CorpusId, Error> {
+        delegate!(self, corpus => corpus.add_disabled(testcase))
+    }
+
+    fn replace(&mut self, id: CorpusId, testcase: Testcase<BytesInput>) -> Result<Testcase<BytesInput>, Error> {
+        delegate!(self, corpus => corpus.replace(id, testcase))
+    }
+
+    fn remove(&mut self, id: CorpusId) -> Result<Testcase<BytesInput>, Error> {
+        delegate!(self, corpus => corpus.remove(id))
+    }
+
+    fn get(&self, id: CorpusId) -> Result<&RefCell<Testcase<BytesInput>>, Error> {
+        delegate!(self, corpus => corpus.get(id))
+    }
+
+    fn get_from_all(&self, id: CorpusId) -> Result<&RefCell<Testcase<BytesInput>>, Error> {
+        delegate!(self, corpus => corpus.get_from_all(id))
+    }
+
+    fn current(&self) -> &Option<CorpusId> {
+        delegate!(self, corpus => corpus.current())
+    }
+
+    fn current_mut(&mut self) -> &mut Option<CorpusId> {
+        delegate!(self, corpus => corpus.current_mut())
+    }
+
+    fn next(&self, id: CorpusId) -> Option<CorpusId> {
+        delegate!(self, corpus => corpus.next(id))
+    }
+
+    fn peek_free_id(&self) -> CorpusId {
+        delegate!(self, corpus => corpus.peek_free_id())
+    }
+
+    fn prev(&self, id: CorpusId) -> Option<CorpusId> {
+        delegate!(self, corpus => corpus.prev(id))
+    }
+
+    fn first(&self) -> Option<CorpusId> {
+        delegate!(self, corpus => corpus.first())
+    }
+
+    fn last(&self) -> Option<CorpusId> {
+        delegate!(self, corpus => corpus.last())
+    }
+
+    fn nth_from_all(&self, nth: usize) -> CorpusId {
+        delegate!(self, corpus => corpus.nth_from_all(nth))
+    }
+
+    fn load_input_into(&self, testcase: &mut Testcase<BytesInput>) -> Result<(), Error> {
+        delegate!(self, corpus => corpus.load_input_into(testcase))
+    }
+
+    fn store_input_from(&self, testcase: &Testcase<BytesInput>) -> Result<(), Error> {
+        delegate!(self, corpus => corpus.store_input_from(testcase))
+    }
+}
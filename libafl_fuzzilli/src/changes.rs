@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+
+use libafl::corpus::CorpusId;
+
+// Net corpus changes since a given version, for hosts that mirror the corpus.
+// An id appears in at most one of the lists.
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct FzilChangeSet {
+    // Pass this back to changes_since() to only get newer changes
+    pub version: u64,
+    pub added: Vec<u64>,
+    // Entries the mirror already had whose content changed
+    pub replaced: Vec<u64>,
+    pub removed: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CorpusChange {
+    Added(CorpusId),
+    Replaced(CorpusId),
+    Removed(CorpusId),
+}
+
+impl CorpusChange {
+    fn id(&self) -> CorpusId {
+        match *self {
+            CorpusChange::Added(id) | CorpusChange::Replaced(id) | CorpusChange::Removed(id) => id,
+        }
+    }
+}
+
+// What happened to one id within a window of changes
+struct NetChange {
+    // In the corpus at the start of the window
+    existed: bool,
+    // In the corpus at the end of it
+    exists: bool,
+    // Content changed while it was there
+    changed: bool,
+}
+
+// Append-only record of corpus changes; the version is the number of changes recorded
+#[derive(Debug, Default)]
+pub(crate) struct ChangeLog {
+    changes: Vec<CorpusChange>,
+}
+
+fn to_u64(id: CorpusId) -> u64 {
+    let id_usize: usize = id.into();
+    id_usize as u64
+}
+
+impl ChangeLog {
+    pub(crate) fn version(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub(crate) fn added(&mut self, id: CorpusId) {
+        self.changes.push(CorpusChange::Added(id));
+    }
+
+    pub(crate) fn replaced(&mut self, id: CorpusId) {
+        self.changes.push(CorpusChange::Replaced(id));
+    }
+
+    pub(crate) fn removed(&mut self, id: CorpusId) {
+        self.changes.push(CorpusChange::Removed(id));
+    }
+
+    // The corpus holding `before` was swapped for one holding `after` (a state restore).
+    // Ids in both are recorded as replaced, since their content may differ.
+    pub(crate) fn swapped(&mut self, before: &[CorpusId], after: &[CorpusId]) {
+        let after_set: HashSet<_> = after.iter().collect();
+        let before_set: HashSet<_> = before.iter().collect();
+        for id in before {
+            if !after_set.contains(id) {
+                self.removed(*id);
+            }
+        }
+        for id in after {
+            if before_set.contains(id) {
+                self.replaced(*id);
+            } else {
+                self.added(*id);
+            }
+        }
+    }
+
+    // Ids added after `version`, in order
+    pub(crate) fn added_since(&self, version: usize) -> impl Iterator<Item = CorpusId> + '_ {
+        self.changes[version.min(self.changes.len())..]
+            .iter()
+            .filter_map(|change| match change {
+                CorpusChange::Added(id) => Some(*id),
+                _ => None,
+            })
+    }
+
+    pub(crate) fn since(&self, version: usize) -> FzilChangeSet {
+        // Ids can come back after a state restore, so each id's changes are folded in order:
+        // whether it existed at `version` follows from its first change in the window
+        let mut order = Vec::new();
+        let mut net: HashMap<CorpusId, NetChange> = HashMap::new();
+        for change in &self.changes[version.min(self.changes.len())..] {
+            let id = change.id();
+            let entry = net.entry(id).or_insert_with(|| {
+                order.push(id);
+                NetChange {
+                    existed: !matches!(change, CorpusChange::Added(_)),
+                    exists: false,
+                    changed: false,
+                }
+            });
+            match change {
+                CorpusChange::Added(_) => {
+                    entry.exists = true;
+                    entry.changed |= entry.existed;
+                }
+                CorpusChange::Replaced(_) => {
+                    entry.exists = true;
+                    entry.changed = true;
+                }
+                CorpusChange::Removed(_) => entry.exists = false,
+            }
+        }
+        let mut added = Vec::new();
+        let mut replaced = Vec::new();
+        let mut removed = Vec::new();
+        for id in order {
+            let change = &net[&id];
+            match (change.existed, change.exists) {
+                (false, true) => added.push(to_u64(id)),
+                (true, true) if change.changed => replaced.push(to_u64(id)),
+                (true, false) => removed.push(to_u64(id)),
+                _ => {}
+            }
+        }
+        FzilChangeSet {
+            version: self.changes.len() as u64,
+            added,
+            replaced,
+            removed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: usize) -> CorpusId {
+        CorpusId::from(n)
+    }
+
+    fn lists(set: &FzilChangeSet) -> (Vec<u64>, Vec<u64>, Vec<u64>) {
+        (set.added.clone(), set.replaced.clone(), set.removed.clone())
+    }
+
+    #[test]
+    fn reports_changes_in_order() {
+        let mut log = ChangeLog::default();
+        log.added(id(0));
+        log.added(id(1));
+        let set = log.since(0);
+        assert_eq!(set.version, 2);
+        assert_eq!(lists(&set), (vec![0, 1], vec![], vec![]));
+        assert_eq!(lists(&log.since(2)), (vec![], vec![], vec![]));
+        // Versions past the end behave like the current one
+        assert_eq!(lists(&log.since(10)), (vec![], vec![], vec![]));
+    }
+
+    #[test]
+    fn add_then_remove_in_window_cancels_out() {
+        let mut log = ChangeLog::default();
+        log.added(id(0));
+        log.added(id(1));
+        log.removed(id(0));
+        assert_eq!(lists(&log.since(0)), (vec![1], vec![], vec![]));
+        // Seen from after the add, it is a plain removal
+        assert_eq!(lists(&log.since(1)), (vec![1], vec![], vec![0]));
+    }
+
+    #[test]
+    fn replace_after_add_in_window_is_an_add() {
+        let mut log = ChangeLog::default();
+        log.added(id(0));
+        log.replaced(id(0));
+        log.replaced(id(0));
+        assert_eq!(lists(&log.since(0)), (vec![0], vec![], vec![]));
+        // Entries the mirror already has are reported replaced once
+        assert_eq!(lists(&log.since(1)), (vec![], vec![0], vec![]));
+    }
+
+    #[test]
+    fn replace_then_remove_is_a_removal() {
+        let mut log = ChangeLog::default();
+        log.added(id(0));
+        log.replaced(id(0));
+        log.removed(id(0));
+        assert_eq!(lists(&log.since(1)), (vec![], vec![], vec![0]));
+    }
+
+    #[test]
+    fn swap_keeps_the_version_growing() {
+        let mut log = ChangeLog::default();
+        log.added(id(0));
+        log.added(id(1));
+        log.added(id(2));
+        log.removed(id(1));
+        let before = log.version();
+        // The restored corpus has 0 and 1, but not 2
+        log.swapped(&[id(0), id(2)], &[id(0), id(1)]);
+        assert!(log.version() > before);
+        assert_eq!(lists(&log.since(before)), (vec![1], vec![0], vec![2]));
+        // Id 1 was removed and came back with the restore, so a mirror from before has
+        // to fetch it again. Ids are listed in the order of their first change in the window.
+        assert_eq!(lists(&log.since(3)), (vec![], vec![1, 0], vec![2]));
+        assert_eq!(lists(&log.since(0)), (vec![0, 1], vec![], vec![]));
+    }
+
+    #[test]
+    fn added_since_skips_other_changes() {
+        let mut log = ChangeLog::default();
+        log.added(id(0));
+        log.replaced(id(0));
+        log.added(id(1));
+        log.removed(id(0));
+        assert_eq!(log.added_since(1).collect::<Vec<_>>(), vec![id(1)]);
+    }
+}
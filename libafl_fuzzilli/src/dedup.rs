@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use ahash::RandomState;
+use libafl_bolts::fs::write_file_atomic;
+
+// Files inside the corpus directory logging the hashes of stored inputs, one record per
+// change: `+` or `-` followed by the little-endian u64 hash. MyFzilScheduler and
+// FzilOnDiskCorpusBytes may share a directory, so each has its own.
+pub(crate) const SEEN_FILE: &str = ".fzil_seen";
+pub(crate) const CORPUS_SEEN_FILE: &str = ".fzil_seen_corpus";
+
+const RECORD_ADD: u8 = b'+';
+const RECORD_REMOVE: u8 = b'-';
+// The log is compacted once it holds this many records more than there are stored hashes
+const COMPACT_SLACK: usize = 1024;
+
+// Content hashes of the inputs in a corpus, plus those still waiting to be written to it.
+// A new corpus object starts out empty (LibAFL does not pick up entries of earlier runs),
+// and so does the set; restore_state resets it to the restored entries. The hashes of stored
+// entries are mirrored to a file next to the corpus, buffered ones only live in memory.
+#[derive(Debug)]
+pub(crate) struct SeenInputs {
+    hashes: HashSet<u64>,
+    // The part of `hashes` not in the file yet
+    unpersisted: HashSet<u64>,
+    // None for corpora that are not on disk
+    file: Option<(PathBuf, File)>,
+    // Records in the file
+    records: usize,
+}
+
+impl SeenInputs {
+    // Starts over with an empty `file_name` in `corpus_dir`, whatever an earlier run left there
+    pub(crate) fn open(corpus_dir: &Path, file_name: &str) -> std::io::Result<SeenInputs> {
+        let path = corpus_dir.join(file_name);
+        let file = File::create(&path)?;
+        Ok(SeenInputs {
+            hashes: HashSet::new(),
+            unpersisted: HashSet::new(),
+            file: Some((path, file)),
+            records: 0,
+        })
+    }
+
+    pub(crate) fn in_memory() -> SeenInputs {
+        SeenInputs {
+            hashes: HashSet::new(),
+            unpersisted: HashSet::new(),
+            file: None,
+            records: 0,
+        }
+    }
+
+    // Same fixed-seed ahash LibAFL uses to name BytesInput files, so it is stable across runs
+    pub(crate) fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = RandomState::with_seeds(0, 0, 0, 0).build_hasher();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    pub(crate) fn contains(&self, bytes: &[u8]) -> bool {
+        self.hashes.contains(&Self::hash(bytes))
+    }
+
+    // Records `bytes` as seen for this session, returns false if it had been seen before.
+    // Call persist once the input is stored in the corpus.
+    pub(crate) fn insert(&mut self, bytes: &[u8]) -> bool {
+        let hash = Self::hash(bytes);
+        if !self.hashes.insert(hash) {
+            return false;
+        }
+        self.unpersisted.insert(hash);
+        true
+    }
+
+    // Writes the hash of an inserted input, now stored in the corpus, to the file
+    pub(crate) fn persist(&mut self, bytes: &[u8]) {
+        let hash = Self::hash(bytes);
+        if self.unpersisted.remove(&hash) {
+            self.append(RECORD_ADD, hash);
+        }
+    }
+
+    // Forgets `bytes`, e.g. because its corpus entry was removed, so it can be added again
+    pub(crate) fn remove(&mut self, bytes: &[u8]) {
+        let hash = Self::hash(bytes);
+        if !self.hashes.remove(&hash) || self.unpersisted.remove(&hash) {
+            return;
+        }
+        self.append(RECORD_REMOVE, hash);
+        if self.records > self.hashes.len() - self.unpersisted.len() + COMPACT_SLACK {
+            self.compact();
+        }
+    }
+
+    fn append(&mut self, op: u8, hash: u64) {
+        let Some((_, file)) = self.file.as_mut() else {
+            return;
+        };
+        let mut record = [0; 9];
+        record[0] = op;
+        record[1..].copy_from_slice(&hash.to_le_bytes());
+        // Still deduplicates in memory for this session if the write fails
+        match file.write_all(&record) {
+            Ok(()) => self.records += 1,
+            Err(e) => println!("Unable to persist input hash: {}", e),
+        }
+    }
+
+    fn compact(&mut self) {
+        // Like append, the in-memory set is what counts
+        if let Err(e) = self.rewrite() {
+            println!("Unable to rewrite input hashes: {}", e);
+        }
+    }
+
+    // Forget everything seen so far and start over with the `stored` entries of a restored
+    // corpus plus the `buffered` inputs not written yet. The file is rewritten to match.
+    pub(crate) fn reset<'a>(
+        &mut self,
+        stored: impl IntoIterator<Item = &'a [u8]>,
+        buffered: impl IntoIterator<Item = &'a [u8]>,
+    ) {
+        self.unpersisted = buffered.into_iter().map(Self::hash).collect();
+        self.hashes = stored.into_iter().map(Self::hash).collect();
+        self.hashes.extend(&self.unpersisted);
+        self.compact();
+    }
+
+    fn rewrite(&mut self) -> std::io::Result<()> {
+        let Some((path, file)) = self.file.as_mut() else {
+            return Ok(());
+        };
+        let mut raw = Vec::new();
+        let mut records = 0;
+        for hash in self.hashes.difference(&self.unpersisted) {
+            raw.push(RECORD_ADD);
+            raw.extend(hash.to_le_bytes());
+            records += 1;
+        }
+        write_file_atomic(&path, &raw).map_err(|e| std::io::Error::other(e.to_string()))?;
+        // The old handle still points at the replaced file
+        *file = OpenOptions::new().append(true).open(&path)?;
+        self.records = records;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fzil_dedup_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_records(dir: &Path) -> usize {
+        std::fs::read(dir.join(SEEN_FILE)).unwrap().len() / 9
+    }
+
+    // Replays the log
+    fn file_hashes(dir: &Path) -> Vec<u64> {
+        let mut hashes = HashSet::new();
+        for record in std::fs::read(dir.join(SEEN_FILE)).unwrap().chunks_exact(9) {
+            let hash = u64::from_le_bytes(record[1..].try_into().unwrap());
+            match record[0] {
+                RECORD_ADD => assert!(hashes.insert(hash)),
+                RECORD_REMOVE => assert!(hashes.remove(&hash)),
+                op => panic!("unknown record {}", op),
+            }
+        }
+        sorted(hashes.into_iter().collect())
+    }
+
+    fn sorted(mut hashes: Vec<u64>) -> Vec<u64> {
+        hashes.sort();
+        hashes
+    }
+
+    #[test]
+    fn insert_reports_duplicates() {
+        let mut seen = SeenInputs::in_memory();
+        assert!(seen.insert(b"a"));
+        assert!(!seen.insert(b"a"));
+        assert!(seen.insert(b"b"));
+    }
+
+    #[test]
+    fn only_persisted_hashes_reach_the_file() {
+        let dir = temp_dir("persist");
+        let mut seen = SeenInputs::open(&dir, SEEN_FILE).unwrap();
+        seen.insert(b"stored");
+        seen.insert(b"buffered");
+        seen.persist(b"stored");
+        // Persisting twice, or something never inserted, writes nothing
+        seen.persist(b"stored");
+        seen.persist(b"unknown");
+        assert_eq!(file_hashes(&dir), vec![SeenInputs::hash(b"stored")]);
+        assert!(!seen.insert(b"buffered"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn new_session_starts_empty() {
+        let dir = temp_dir("session");
+        let mut seen = SeenInputs::open(&dir, SEEN_FILE).unwrap();
+        seen.insert(b"a");
+        seen.persist(b"a");
+        drop(seen);
+        let mut seen = SeenInputs::open(&dir, SEEN_FILE).unwrap();
+        assert!(file_hashes(&dir).is_empty());
+        assert!(seen.insert(b"a"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn remove_allows_adding_again() {
+        let dir = temp_dir("remove");
+        let mut seen = SeenInputs::open(&dir, SEEN_FILE).unwrap();
+        for input in [&b"a"[..], b"b"] {
+            seen.insert(input);
+            seen.persist(input);
+        }
+        seen.insert(b"buffered");
+        seen.remove(b"a");
+        seen.remove(b"buffered");
+        assert_eq!(file_hashes(&dir), vec![SeenInputs::hash(b"b")]);
+        assert!(seen.insert(b"a"));
+        assert!(seen.insert(b"buffered"));
+        assert!(!seen.insert(b"b"));
+        seen.persist(b"a");
+        assert_eq!(file_hashes(&dir), sorted(vec![SeenInputs::hash(b"a"), SeenInputs::hash(b"b")]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn removals_compact_the_file() {
+        let dir = temp_dir("compact");
+        let mut seen = SeenInputs::open(&dir, SEEN_FILE).unwrap();
+        seen.insert(b"kept");
+        seen.persist(b"kept");
+        for i in 0..3 * COMPACT_SLACK as u32 {
+            let input = i.to_le_bytes();
+            seen.insert(&input);
+            seen.persist(&input);
+            seen.remove(&input);
+        }
+        assert!(file_records(&dir) <= 1 + COMPACT_SLACK + 1);
+        assert_eq!(file_hashes(&dir), vec![SeenInputs::hash(b"kept")]);
+        // Appends keep working after the file was rewritten
+        seen.insert(b"new");
+        seen.persist(b"new");
+        assert_eq!(file_hashes(&dir), sorted(vec![SeenInputs::hash(b"kept"), SeenInputs::hash(b"new")]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reset_replaces_everything() {
+        let dir = temp_dir("reset");
+        let mut seen = SeenInputs::open(&dir, SEEN_FILE).unwrap();
+        seen.insert(b"old");
+        seen.persist(b"old");
+        seen.reset([&b"stored"[..]], [&b"buffered"[..]]);
+        assert_eq!(file_hashes(&dir), vec![SeenInputs::hash(b"stored")]);
+        assert!(seen.insert(b"old"));
+        assert!(!seen.insert(b"stored"));
+        assert!(!seen.insert(b"buffered"));
+        // The buffered input reaches the file once it is stored
+        seen.persist(b"buffered");
+        assert_eq!(
+            file_hashes(&dir),
+            sorted(vec![SeenInputs::hash(b"stored"), SeenInputs::hash(b"buffered")])
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,38 @@
+use thiserror::Error;
+
+// Errors surfaced to the host through uniffi instead of aborting the Fuzzilli process
+#[derive(Debug, Error, uniffi::Error)]
+pub enum FuzzilliBridgeError {
+    #[error("cannot open corpus directory {path}: {reason}")]
+    CorpusDir { path: String, reason: String },
+    #[error("the corpus is empty")]
+    EmptyCorpus,
+    #[error("no corpus entry with id {id}")]
+    UnknownId { id: u64 },
+    #[error("corpus entry {id} is not a valid FuzzIL program: {reason}")]
+    InvalidProgram { id: u64, reason: String },
+    #[error("the replacement for corpus entry {id} is already in the corpus")]
+    DuplicateInput { id: u64 },
+    #[error("invalid state snapshot {path}: {reason}")]
+    InvalidSnapshot { path: String, reason: String },
+    #[error("I/O error: {reason}")]
+    Io { reason: String },
+    #[error("LibAFL error: {reason}")]
+    LibAfl { reason: String },
+}
+
+impl From<libafl_bolts::Error> for FuzzilliBridgeError {
+    fn from(err: libafl_bolts::Error) -> Self {
+        FuzzilliBridgeError::LibAfl {
+            reason: err.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for FuzzilliBridgeError {
+    fn from(err: std::io::Error) -> Self {
+        FuzzilliBridgeError::Io {
+            reason: err.to_string(),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use libafl::corpus::CorpusId;
+use serde::{Deserialize, Serialize};
+
+use crate::stats::now_ms;
+
+// What happened to a corpus entry, or to corpus writes as a whole
+#[derive(uniffi::Enum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FzilEventKind {
+    Add,
+    Suggest,
+    // `id` refers to the solutions corpus
+    Crash,
+    Remove,
+    Replace,
+    // `len` is the snapshot size, `version` the changes_since() version it covers
+    Checkpoint,
+    // Writing to the corpus started failing, inputs are buffered in memory from now on
+    WriteFailed,
+    // The overflow buffer was flushed and corpus writes work again
+    WriteRecovered,
+}
+
+// One line of the event log
+#[derive(uniffi::Record, Serialize, Deserialize, Clone, Debug)]
+pub struct FzilEvent {
+    pub event: FzilEventKind,
+    pub id: u64,
+    pub len: u64,
+    pub ts_ms: u64,
+    // Inputs in the overflow buffer, only for WriteFailed and WriteRecovered (whose `id`
+    // and `len` are 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<u64>,
+    // Only for Checkpoint (whose `id` is 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+}
+
+// Mirrors scheduler activity as one JSON object per line, for log-ingestion pipelines
+// and for post-hoc reconstruction of a run. File logs are rotated once they reach
+// `rotate_bytes`, keeping a single previous generation as `<path>.1`.
+pub(crate) struct FzilEventLog {
+    sink: Box<dyn Write + Send>,
+    // Only set for file targets, sockets cannot be rotated or read back
+    path: Option<PathBuf>,
+    written: u64,
+    rotate_bytes: u64,
+}
+
+impl core::fmt::Debug for FzilEventLog {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FzilEventLog")
+            .field("path", &self.path)
+            .field("written", &self.written)
+            .field("rotate_bytes", &self.rotate_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+// Lines a socket target may fall behind by before new ones are dropped
+const TCP_BACKLOG_LINES: usize = 4096;
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Hands lines to a writer thread, so a slow or stalled collector never blocks the scheduler
+// (events are recorded under its locks). Lines that do not fit in the backlog are dropped.
+struct TcpSink {
+    sender: SyncSender<Vec<u8>>,
+    // Whether the line before the current one was dropped, to report drops once per burst
+    dropping: bool,
+}
+
+impl TcpSink {
+    fn connect(addr: &str) -> std::io::Result<TcpSink> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("cannot resolve {}", addr)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, TCP_CONNECT_TIMEOUT)?;
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(TCP_BACKLOG_LINES);
+        // Ends once the sink is dropped, or when the connection fails
+        thread::spawn(move || {
+            for line in receiver {
+                if let Err(e) = stream.write_all(&line) {
+                    println!("Event log connection to {} lost: {}", addr, e);
+                    return;
+                }
+            }
+        });
+        Ok(TcpSink { sender, dropping: false })
+    }
+}
+
+impl Write for TcpSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.sender.try_send(buf.to_vec()) {
+            Ok(()) => self.dropping = false,
+            Err(TrySendError::Full(_)) => {
+                if !self.dropping {
+                    println!("Event log collector is falling behind, dropping events");
+                }
+                self.dropping = true;
+            }
+            Err(TrySendError::Disconnected(_)) => return Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+impl FzilEventLog {
+    // `target` is either `tcp://host:port` or a file path (appended to).
+    // A `rotate_bytes` of 0 never rotates.
+    pub(crate) fn open(target: &str, rotate_bytes: u64) -> std::io::Result<FzilEventLog> {
+        if let Some(addr) = target.strip_prefix("tcp://") {
+            return Ok(FzilEventLog {
+                sink: Box::new(TcpSink::connect(addr)?),
+                path: None,
+                written: 0,
+                rotate_bytes: 0,
+            });
+        }
+        let path = PathBuf::from(target);
+        let file = open_append(&path)?;
+        let written = file.metadata()?.len();
+        Ok(FzilEventLog {
+            sink: Box::new(file),
+            path: Some(path),
+            written,
+            rotate_bytes,
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: FzilEventKind, id: CorpusId, len: usize) {
+        let id_usize: usize = id.into();
+        self.write(FzilEvent {
+            event,
+            id: id_usize as u64,
+            len: len as u64,
+            ts_ms: now_ms(),
+            pending: None,
+            version: None,
+        });
+    }
+
+    pub(crate) fn record_checkpoint(&mut self, version: u64, len: usize) {
+        self.write(FzilEvent {
+            event: FzilEventKind::Checkpoint,
+            id: 0,
+            len: len as u64,
+            ts_ms: now_ms(),
+            pending: None,
+            version: Some(version),
+        });
+    }
+
+    pub(crate) fn record_health(&mut self, event: FzilEventKind, pending: usize) {
+        self.write(FzilEvent {
+            event,
+            id: 0,
+            len: 0,
+            ts_ms: now_ms(),
+            pending: Some(pending as u64),
+            version: None,
+        });
+    }
+
+    fn write(&mut self, event: FzilEvent) {
+        let mut line = serde_json::to_vec(&event).expect("events always serialize");
+        line.push(b'\n');
+
+        // Logging must never take the fuzzer down, so errors are only reported
+        if let Err(e) = self.rotate_if_needed(line.len() as u64) {
+            println!("Unable to rotate event log: {}", e);
+        }
+        match self.sink.write_all(&line) {
+            Ok(()) => self.written += line.len() as u64,
+            Err(e) => println!("Unable to write to event log: {}", e),
+        }
+    }
+
+    fn rotate_if_needed(&mut self, incoming: u64) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if self.rotate_bytes == 0 || self.written == 0 || self.written + incoming <= self.rotate_bytes {
+            return Ok(());
+        }
+        std::fs::rename(path, rotated_path(path))?;
+        self.sink = Box::new(open_append(path)?);
+        self.written = 0;
+        Ok(())
+    }
+
+    // Path of the current log file, with everything recorded so far flushed to it.
+    // None for socket targets.
+    pub(crate) fn flushed_path(&mut self) -> Option<PathBuf> {
+        let path = self.path.clone()?;
+        let _ = self.sink.flush();
+        Some(path)
+    }
+}
+
+// Events recorded at or after `ts_ms`, oldest first, read back from the log file at `path`
+// and its previous generation. Called without the log locked, so events recorded meanwhile
+// may or may not show up, and a rotation in between can hide the events it moved.
+pub(crate) fn events_since(path: &Path, ts_ms: u64) -> Vec<FzilEvent> {
+    let mut events = Vec::new();
+    for file_path in [rotated_path(path), path.to_path_buf()] {
+        let Ok(file) = File::open(&file_path) else {
+            continue;
+        };
+        // Skip lines that do not parse, e.g. one cut short by a crash mid-write
+        events.extend(
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str::<FzilEvent>(&line).ok())
+                .filter(|event| event.ts_ms >= ts_ms),
+        );
+    }
+    events
+}
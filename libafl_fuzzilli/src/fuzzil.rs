@@ -0,0 +1,185 @@
+// Just enough of the protobuf wire format to look inside the serialized FuzzIL programs
+// Fuzzilli hands us (`Fuzzilli_Protobuf_Program` from program.proto):
+//
+//     message Program {
+//         bytes uuid = 1;
+//         repeated Instruction code = 2;
+//         map<sint32, string> comments = 3;
+//         Program parent = 4;
+//     }
+//
+// Unknown fields are skipped, so newer Fuzzilli versions still decode.
+
+const CODE_FIELD: u64 = 2;
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LEN: u64 = 2;
+const WIRE_FIXED32: u64 = 5;
+
+// The parts of a program envelope the bridge cares about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FuzzIlProgram {
+    // Number of instructions in the program, the size that matters for FuzzIL
+    pub(crate) instructions: usize,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| format!("truncated varint at byte {}", self.pos))?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(format!("varint longer than 10 bytes at byte {}", self.pos))
+    }
+
+    fn skip(&mut self, len: u64) -> Result<&'a [u8], String> {
+        let start = self.pos;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("field at byte {} runs past the end of the program", start))?;
+        self.pos = end;
+        Ok(&self.bytes[start..end])
+    }
+}
+
+// Decodes the envelope, rejecting anything that is not well-formed protobuf
+pub(crate) fn decode_program(bytes: &[u8]) -> Result<FuzzIlProgram, String> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let mut instructions = 0;
+    while reader.pos < bytes.len() {
+        let key = reader.varint()?;
+        let (field, wire_type) = (key >> 3, key & 7);
+        if field == 0 {
+            return Err(format!("invalid field number 0 at byte {}", reader.pos));
+        }
+        if field == CODE_FIELD && wire_type != WIRE_LEN {
+            return Err(format!("instruction with wire type {} at byte {}", wire_type, reader.pos));
+        }
+        match wire_type {
+            WIRE_VARINT => {
+                reader.varint()?;
+            }
+            WIRE_FIXED64 => {
+                reader.skip(8)?;
+            }
+            WIRE_LEN => {
+                let len = reader.varint()?;
+                reader.skip(len)?;
+                if field == CODE_FIELD {
+                    instructions += 1;
+                }
+            }
+            WIRE_FIXED32 => {
+                reader.skip(4)?;
+            }
+            _ => return Err(format!("unsupported wire type {} for field {}", wire_type, field)),
+        }
+    }
+    Ok(FuzzIlProgram { instructions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A program serialized by Fuzzilli: 24 instructions plus uuid, comments and a parent
+    const PROGRAM: &[u8] = include_bytes!("../fzilfile.fzil");
+
+    #[test]
+    fn decodes_fuzzilli_program() {
+        assert_eq!(decode_program(PROGRAM), Ok(FuzzIlProgram { instructions: 24 }));
+    }
+
+    #[test]
+    fn empty_program_has_no_instructions() {
+        assert_eq!(decode_program(&[]), Ok(FuzzIlProgram { instructions: 0 }));
+    }
+
+    #[test]
+    fn skips_unknown_fields() {
+        // field 5 varint, field 6 fixed64, field 7 fixed32, then one instruction
+        let mut bytes = vec![0x28, 0x96, 0x01, 0x31];
+        bytes.extend([0; 8]);
+        bytes.push(0x3d);
+        bytes.extend([0; 4]);
+        bytes.extend([0x12, 0x01, 0x00]);
+        assert_eq!(decode_program(&bytes), Ok(FuzzIlProgram { instructions: 1 }));
+    }
+
+    #[test]
+    fn rejects_truncated_varint() {
+        // Key, then a length whose continuation bit promises more bytes
+        let err = decode_program(&[0x12, 0x80]).unwrap_err();
+        assert!(err.contains("truncated varint"), "{}", err);
+        // Truncated inside the key itself
+        assert!(decode_program(&[0x92]).unwrap_err().contains("truncated varint"));
+    }
+
+    #[test]
+    fn rejects_overlong_varint() {
+        let mut bytes = vec![0x08];
+        bytes.extend([0xff; 10]);
+        bytes.push(0x01);
+        assert!(decode_program(&bytes).unwrap_err().contains("longer than 10 bytes"));
+    }
+
+    #[test]
+    fn rejects_length_past_end() {
+        // Instruction claiming 5 bytes with only 2 left
+        let err = decode_program(&[0x12, 0x05, 0x00, 0x00]).unwrap_err();
+        assert!(err.contains("runs past the end"), "{}", err);
+        // A length that does not fit in usize arithmetic
+        let mut bytes = vec![0x12];
+        bytes.extend([0xff; 9]);
+        bytes.push(0x01);
+        assert!(decode_program(&bytes).unwrap_err().contains("runs past the end"));
+        // Fixed-width fields cut short
+        assert!(decode_program(&[0x31, 0x00]).unwrap_err().contains("runs past the end"));
+        assert!(decode_program(&[0x3d, 0x00]).unwrap_err().contains("runs past the end"));
+    }
+
+    #[test]
+    fn rejects_truncated_real_program() {
+        assert!(decode_program(&PROGRAM[..PROGRAM.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_field_zero() {
+        let err = decode_program(&[0x00, 0x00]).unwrap_err();
+        assert!(err.contains("field number 0"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_instruction_with_wrong_wire_type() {
+        // Field 2 as a varint
+        let err = decode_program(&[0x10, 0x01]).unwrap_err();
+        assert!(err.contains("instruction with wire type 0"), "{}", err);
+        // Field 2 as fixed32
+        assert!(decode_program(&[0x15, 0, 0, 0, 0]).unwrap_err().contains("wire type 5"));
+    }
+
+    #[test]
+    fn rejects_unsupported_wire_types() {
+        // Groups (3 and 4) and the undefined 6 and 7, on an unknown field
+        for wire_type in [3, 4, 6, 7] {
+            let err = decode_program(&[(5 << 3) | wire_type]).unwrap_err();
+            assert!(err.contains("unsupported wire type"), "{}", err);
+        }
+    }
+}
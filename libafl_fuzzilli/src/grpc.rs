@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use crate::{FuzzilliBridgeError, FzilAddVerdict, FzilExecStatus, MyFzilScheduler};
+
+mod proto {
+    tonic::include_proto!("fuzzilli.bridge");
+}
+
+use proto::bridge_server::{Bridge, BridgeServer};
+use proto::{
+    add_input_reply::Verdict, AddInputReply, AddInputRequest, GetStatsReply, GetStatsRequest, ReportExecutionReply,
+    ReportExecutionRequest, SuggestNextReply, SuggestNextRequest,
+};
+
+impl From<FuzzilliBridgeError> for Status {
+    fn from(err: FuzzilliBridgeError) -> Self {
+        match err {
+            FuzzilliBridgeError::EmptyCorpus => Status::failed_precondition(err.to_string()),
+            FuzzilliBridgeError::UnknownId { .. } => Status::not_found(err.to_string()),
+            FuzzilliBridgeError::DuplicateInput { .. } => Status::already_exists(err.to_string()),
+            _ => Status::internal(err.to_string()),
+        }
+    }
+}
+
+// Serves the proto/bridge.proto service by calling into the scheduler
+struct BridgeService {
+    scheduler: Arc<MyFzilScheduler>,
+}
+
+impl BridgeService {
+    // Scheduler calls wait on its locks and on disk I/O, so they run on tokio's blocking
+    // pool instead of stalling the runtime workers
+    async fn call<T, F>(&self, f: F) -> Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&MyFzilScheduler) -> T + Send + 'static,
+    {
+        let scheduler = Arc::clone(&self.scheduler);
+        tokio::task::spawn_blocking(move || f(&scheduler))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl Bridge for BridgeService {
+    async fn add_input(&self, request: Request<AddInputRequest>) -> Result<Response<AddInputReply>, Status> {
+        let input = request.into_inner().input;
+        let reply = match self.call(move |scheduler| scheduler.add_input(input)).await? {
+            FzilAddVerdict::Added { id } => AddInputReply {
+                verdict: Verdict::Added.into(),
+                id: Some(id),
+                reason: String::new(),
+            },
+            FzilAddVerdict::Duplicate => AddInputReply {
+                verdict: Verdict::Duplicate.into(),
+                ..Default::default()
+            },
+            FzilAddVerdict::Buffered => AddInputReply {
+                verdict: Verdict::Buffered.into(),
+                ..Default::default()
+            },
+            FzilAddVerdict::Dropped => AddInputReply {
+                verdict: Verdict::Dropped.into(),
+                ..Default::default()
+            },
+            FzilAddVerdict::Rejected { reason } => AddInputReply {
+                verdict: Verdict::Rejected.into(),
+                id: None,
+                reason,
+            },
+        };
+        Ok(Response::new(reply))
+    }
+
+    async fn suggest_next(&self, _: Request<SuggestNextRequest>) -> Result<Response<SuggestNextReply>, Status> {
+        let suggestion = self.call(|scheduler| scheduler.suggest_next()).await??;
+        Ok(Response::new(SuggestNextReply {
+            id: suggestion.id,
+            input: suggestion.bytes,
+        }))
+    }
+
+    async fn report_execution(
+        &self,
+        request: Request<ReportExecutionRequest>,
+    ) -> Result<Response<ReportExecutionReply>, Status> {
+        let request = request.into_inner();
+        let status = FzilExecStatus {
+            exit_code: request.exit_code,
+            signal: request.signal,
+            timed_out: request.timed_out,
+        };
+        let solution_id = self
+            .call(move |scheduler| scheduler.report_execution(request.input, status))
+            .await??;
+        Ok(Response::new(ReportExecutionReply { solution_id }))
+    }
+
+    async fn get_stats(&self, _: Request<GetStatsRequest>) -> Result<Response<GetStatsReply>, Status> {
+        let stats = self.call(|scheduler| scheduler.get_stats()).await?;
+        Ok(Response::new(GetStatsReply {
+            corpus_size: stats.corpus_size,
+            solutions_count: stats.solutions_count,
+            pending_writes: stats.pending_writes,
+            total_adds: stats.total_adds,
+            duplicates_rejected: stats.duplicates_rejected,
+            invalid_rejected: stats.invalid_rejected,
+            adds_last_minute: stats.adds_last_minute,
+            total_suggestions: stats.total_suggestions,
+            distinct_scheduled: stats.distinct_scheduled,
+            most_scheduled_id: stats.most_scheduled_id,
+            most_scheduled_count: stats.most_scheduled_count,
+            last_add_ts_ms: stats.last_add_ts_ms,
+        }))
+    }
+}
+
+#[uniffi::export]
+impl MyFzilScheduler {
+    // Serve add_input/suggest_next/report_execution/get_stats over gRPC on `addr`
+    // (e.g. `0.0.0.0:50051`) from a background thread, for Fuzzilli instances that do not
+    // link the library. Binding errors are returned here; the server runs until the process exits.
+    pub fn serve_grpc(self: Arc<Self>, addr: String) -> Result<(), FuzzilliBridgeError> {
+        let runtime = tokio::runtime::Runtime::new()?;
+        let listener = runtime.block_on(tokio::net::TcpListener::bind(&addr))?;
+        let incoming = tonic::transport::server::TcpIncoming::from_listener(listener, true, None)
+            .map_err(|e| FuzzilliBridgeError::Io { reason: e.to_string() })?;
+        let service = BridgeServer::new(BridgeService { scheduler: self });
+        std::thread::spawn(move || {
+            let server = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming);
+            if let Err(e) = runtime.block_on(server) {
+                println!("gRPC server on {} stopped: {}", addr, e);
+            }
+        });
+        Ok(())
+    }
+}
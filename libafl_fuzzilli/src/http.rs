@@ -0,0 +1,135 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::{FuzzilliBridgeError, MyFzilScheduler};
+
+// Applies to each read and write, so a client trickling bytes in can keep its slot longer
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+// Connections served at once; further ones are turned away until a slot frees up
+const MAX_CLIENTS: usize = 16;
+// The request line and headers are read up to this size, the rest is ignored
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+
+// A taken connection slot, given back when dropped
+struct ClientSlot(Arc<AtomicUsize>);
+
+impl ClientSlot {
+    fn take(active: &Arc<AtomicUsize>) -> Option<ClientSlot> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < MAX_CLIENTS).then_some(n + 1))
+            .ok()?;
+        Some(ClientSlot(Arc::clone(active)))
+    }
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+struct HttpResponse {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn text(status: &'static str, body: String) -> HttpResponse {
+        HttpResponse {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    }
+}
+
+// Read-only status pages for operators, each connection on its own thread (up to
+// MAX_CLIENTS) so an idle client cannot hold up the others:
+//   GET /stats        get_stats() as JSON
+//   GET /corpus/<id>  the raw input of a corpus entry
+pub(crate) fn serve(listener: TcpListener, scheduler: Arc<MyFzilScheduler>) {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let Some(slot) = ClientSlot::take(&active) else {
+            // Small enough for the socket buffer, so this does not block the accept loop
+            let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+            let busy = HttpResponse::text("503 Service Unavailable", "too many clients\n".to_string());
+            let _ = write_response(&mut stream, &busy);
+            continue;
+        };
+        let scheduler = Arc::clone(&scheduler);
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = handle(stream, &scheduler) {
+                println!("Status endpoint request failed: {}", e);
+            }
+        });
+    }
+}
+
+fn handle(mut stream: TcpStream, scheduler: &MyFzilScheduler) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers, nothing in them matters here
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) => route(path, scheduler),
+        _ => HttpResponse::text("405 Method Not Allowed", "only GET is supported\n".to_string()),
+    };
+
+    write_response(&mut stream, &response)
+}
+
+fn write_response(stream: &mut TcpStream, response: &HttpResponse) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)
+}
+
+fn route(path: &str, scheduler: &MyFzilScheduler) -> HttpResponse {
+    if path == "/stats" {
+        let stats = serde_json::to_vec_pretty(&scheduler.get_stats()).expect("stats always serialize");
+        return HttpResponse {
+            status: "200 OK",
+            content_type: "application/json",
+            body: stats,
+        };
+    }
+    if let Some(id) = path.strip_prefix("/corpus/") {
+        let Ok(id) = id.parse::<u64>() else {
+            return HttpResponse::text("400 Bad Request", format!("invalid corpus id {}\n", id));
+        };
+        return match scheduler.get_entry(id) {
+            Ok(bytes) => HttpResponse {
+                status: "200 OK",
+                content_type: "application/octet-stream",
+                body: bytes,
+            },
+            Err(e @ FuzzilliBridgeError::UnknownId { .. }) => HttpResponse::text("404 Not Found", format!("{}\n", e)),
+            Err(e) => HttpResponse::text("500 Internal Server Error", format!("{}\n", e)),
+        };
+    }
+    HttpResponse::text("404 Not Found", format!("no such page {}\n", path))
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use libafl::{
+    corpus::{CorpusId, Testcase},
+    inputs::{BytesInput, HasMutatorBytes},
+};
+
+// Where the input of a corpus entry can be read from without going through the corpus
+#[derive(Debug, Clone)]
+enum InputSource {
+    File(PathBuf),
+    // Entries of in-memory corpora have no file, their bytes are copied here
+    Bytes(Arc<[u8]>),
+}
+
+// Bytes copied out of in-memory entries at most; entries past it are not indexed, so
+// lookups of them lock the corpus every time instead of doubling its memory
+const MAX_COPIED_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+struct Sources {
+    by_id: HashMap<CorpusId, InputSource>,
+    // Total size of the Bytes sources
+    copied: usize,
+}
+
+// Lets entry lookups copy inputs out under their own read lock. Testcases sit in RefCells,
+// which may not be borrowed from several threads at once, so any lookup that has to touch
+// the corpus needs it locked exclusively. Filled on first lookup of an entry.
+#[derive(Debug, Default)]
+pub(crate) struct InputIndex {
+    sources: RwLock<Sources>,
+}
+
+impl InputIndex {
+    // None if the entry is not indexed (yet, or past MAX_COPIED_BYTES), or its file has
+    // gone away since
+    pub(crate) fn get(&self, id: CorpusId) -> Option<Vec<u8>> {
+        let source = self.sources.read().unwrap().by_id.get(&id).cloned()?;
+        match source {
+            InputSource::File(path) => std::fs::read(path).ok(),
+            InputSource::Bytes(bytes) => Some(bytes.to_vec()),
+        }
+    }
+
+    pub(crate) fn insert(&self, id: CorpusId, testcase: &Testcase<BytesInput>) {
+        let mut sources = self.sources.write().unwrap();
+        let source = match (testcase.file_path(), testcase.input()) {
+            (Some(path), _) => InputSource::File(path.clone()),
+            (None, Some(input)) if sources.copied + input.bytes().len() <= MAX_COPIED_BYTES => {
+                InputSource::Bytes(input.bytes().into())
+            }
+            _ => return,
+        };
+        if let InputSource::Bytes(bytes) = &source {
+            sources.copied += bytes.len();
+        }
+        if let Some(InputSource::Bytes(old)) = sources.by_id.insert(id, source) {
+            sources.copied -= old.len();
+        }
+    }
+
+    // Must be called whenever the entry is removed or its content changes
+    pub(crate) fn remove(&self, id: CorpusId) {
+        let mut sources = self.sources.write().unwrap();
+        if let Some(InputSource::Bytes(old)) = sources.by_id.remove(&id) {
+            sources.copied -= old.len();
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.sources.write().unwrap() = Sources::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testcase(len: usize) -> Testcase<BytesInput> {
+        Testcase::new(BytesInput::new(vec![7; len]))
+    }
+
+    #[test]
+    fn copies_stay_within_budget() {
+        let index = InputIndex::default();
+        let half = MAX_COPIED_BYTES / 2;
+        index.insert(CorpusId::from(0usize), &testcase(half));
+        index.insert(CorpusId::from(1usize), &testcase(half));
+        // Over budget, left to the corpus
+        index.insert(CorpusId::from(2usize), &testcase(1));
+        assert_eq!(index.get(CorpusId::from(0usize)).map(|bytes| bytes.len()), Some(half));
+        assert!(index.get(CorpusId::from(2usize)).is_none());
+        // Removing or replacing an entry gives its bytes back
+        index.remove(CorpusId::from(0usize));
+        index.insert(CorpusId::from(1usize), &testcase(1));
+        index.insert(CorpusId::from(2usize), &testcase(half));
+        assert_eq!(index.get(CorpusId::from(2usize)).map(|bytes| bytes.len()), Some(half));
+        index.clear();
+        assert!(index.get(CorpusId::from(1usize)).is_none());
+        assert_eq!(index.sources.read().unwrap().copied, 0);
+    }
+}
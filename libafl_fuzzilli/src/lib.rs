@@ -7,8 +7,10 @@ use core::{
 };
 #[cfg(feature = "std")]
 use std::{fs::File, io::Read, path::Path, path::PathBuf};
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
 use std::marker::PhantomData;
+use std::time::Duration;
 use ahash::RandomState;
 #[cfg(feature = "std")]
 use libafl_bolts::{fs::write_file_atomic, Error};
@@ -16,27 +18,238 @@ use libafl_bolts::{ownedref::OwnedSlice, HasLen};
 use serde::{Deserialize, Serialize};
 use libafl::monitors::SimpleMonitor;
 use libafl::{
-    corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase, HasTestcase, OnDiskCorpus},
+    corpus::{ondisk::OnDiskMetadata, CachedOnDiskCorpus, Corpus, CorpusId, InMemoryCorpus, Testcase, HasTestcase, OnDiskCorpus},
     events::SimpleEventManager,
     executors::{inprocess::InProcessExecutor, ExitKind},
     feedbacks::{CrashFeedback, ConstFeedback, MaxMapFeedback},
     fuzzer::{Fuzzer, StdFuzzer},
     generators::RandPrintablesGenerator,
     inputs::{BytesInput, HasTargetBytes, HasMutatorBytes, Input},
-    mutators::scheduled::{havoc_mutations, StdScheduledMutator},
+    mutators::{
+        scheduled::{
+            havoc_mutations, havoc_mutations_no_crossover, HavocMutationsNoCrossoverType, HavocMutationsType,
+            StdScheduledMutator,
+        },
+        Mutator,
+    },
     observers::StdMapObserver,
-    schedulers::{QueueScheduler, Scheduler},
+    schedulers::{QueueScheduler, RemovableScheduler, Scheduler},
     stages::mutational::StdMutationalStage,
-    state::{StdState,HasCorpus,State, UsesState},
+    state::{StdState,HasCorpus,HasSolutions,State, UsesState},
+    HasMetadata,
 };
 
 use libafl_bolts::{current_nanos, rands::StdRand, tuples::tuple_list, AsSlice};
 use rand::Rng;
 
+mod backend;
+mod changes;
+mod dedup;
+mod error;
+mod events;
+mod fuzzil;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod http;
+mod input_index;
+mod listener;
+mod sample;
+mod maintenance;
+mod stats;
+mod sync;
+pub use error::FuzzilliBridgeError;
+pub use backend::FzilCorpusBackend;
+use backend::MainCorpus;
+pub use events::{FzilEvent, FzilEventKind};
+use events::FzilEventLog;
+pub use listener::FzilListener;
+use listener::{ListenerHandle, Notification};
+pub use maintenance::FzilMaintenanceReport;
+use maintenance::MaintenanceWindow;
+use dedup::SeenInputs;
+use input_index::InputIndex;
+pub use changes::FzilChangeSet;
+use changes::ChangeLog;
+pub use stats::FzilStats;
+use stats::{now_ms, StatsTracker};
+pub use sync::FzilSyncReport;
+use sync::SyncDir;
+use sample::sample_ids;
+
 fn print_type_of<T>(_: &T) {
     println!("{}", std::any::type_name::<T>())
 }
 
+// File name used whenever an entry is written out of the corpus (exports, sync queues)
+pub(crate) fn entry_file_name(id: CorpusId) -> String {
+    let id_usize: usize = id.into();
+    format!("id_{:06}", id_usize)
+}
+
+// Where the main corpus lives unless it is kept in memory
+const CORPUS_DIR: &str = "./pcorpus";
+
+fn open_disk_corpus(dir: &str) -> Result<OnDiskCorpus<BytesInput>, FuzzilliBridgeError> {
+    OnDiskCorpus::new(PathBuf::from(dir)).map_err(|e| FuzzilliBridgeError::CorpusDir {
+        path: dir.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+// Where a corpus entry came from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum EntryOrigin {
+    // Handed to us by the host
+    Local,
+    // Picked up from a sibling instance's sync queue
+    Imported,
+}
+
+impl EntryOrigin {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntryOrigin::Local => "local",
+            EntryOrigin::Imported => "imported",
+        }
+    }
+}
+
+// Stored on every corpus entry, and with it in the entry's .metadata sidecar
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EntryMetadata {
+    origin: EntryOrigin,
+    // Milliseconds since the epoch at which the bridge received the input
+    discovered_ms: u64,
+}
+
+libafl_bolts::impl_serdeany!(EntryMetadata);
+
+// An input on its way into an on-disk corpus, together with what the host told us about it
+#[derive(Debug)]
+struct PendingInput {
+    bytes: Vec<u8>,
+    exec_time: Option<Duration>,
+    origin: EntryOrigin,
+    discovered_ms: u64,
+    // None lets LibAFL name the file after the content hash
+    file_name: Option<String>,
+}
+
+impl PendingInput {
+    fn new(bytes: Vec<u8>) -> PendingInput {
+        PendingInput {
+            bytes,
+            exec_time: None,
+            origin: EntryOrigin::Local,
+            discovered_ms: now_ms(),
+            file_name: None,
+        }
+    }
+
+    fn imported(bytes: Vec<u8>) -> PendingInput {
+        PendingInput {
+            origin: EntryOrigin::Imported,
+            ..PendingInput::new(bytes)
+        }
+    }
+
+    // AFL-style name carrying the discovery time, origin and content hash, e.g.
+    // `time:1792203403278,origin:imported,hash:95ae82a40cabb40c`. Inputs are deduplicated
+    // before they are stored, so the hash keeps names unique.
+    fn descriptive_name(&self) -> String {
+        format!(
+            "time:{},origin:{},hash:{}",
+            self.discovered_ms,
+            self.origin.as_str(),
+            BytesInput::new(self.bytes.clone()).generate_name(None)
+        )
+    }
+
+    fn testcase(&self) -> Testcase<BytesInput> {
+        let mut testcase = Testcase::new(BytesInput::new(self.bytes.clone()));
+        if let Some(exec_time) = self.exec_time {
+            testcase.set_exec_time(exec_time);
+        }
+        testcase.add_metadata(EntryMetadata {
+            origin: self.origin,
+            discovered_ms: self.discovered_ms,
+        });
+        *testcase.filename_mut() = self.file_name.clone();
+        testcase
+    }
+}
+
+// What happened to an input handed to add_input
+#[derive(uniffi::Enum, Debug, Clone, PartialEq, Eq)]
+pub enum FzilAddVerdict {
+    // Stored in the corpus under `id`
+    Added { id: u64 },
+    // The same bytes were added before, nothing was stored
+    Duplicate,
+    // Writing to disk failed; kept in memory until a later flush succeeds
+    Buffered,
+    // Writing to disk failed and the overflow buffer is full, nothing was stored
+    Dropped,
+    // Not a well-formed FuzzIL program (only with set_validate_programs)
+    Rejected { reason: String },
+}
+
+impl FzilAddVerdict {
+    fn added(id: CorpusId) -> FzilAddVerdict {
+        let id_usize: usize = id.into();
+        FzilAddVerdict::Added { id: id_usize as u64 }
+    }
+}
+
+// Adds `testcase` to a corpus. OnDiskCorpus registers the entry in memory before
+// writing it out, so if the write fails (disk full, permissions) the half-added entry is
+// rolled back; callers keep their own copy of the input if they want to retry later.
+fn try_add_to_disk<C: Corpus<Input = BytesInput>>(corpus: &mut C, testcase: Testcase<BytesInput>) -> Result<CorpusId, Error> {
+    let last_before = corpus.last();
+    corpus.add(testcase).inspect_err(|_| {
+        if let Some(id) = corpus.last().filter(|id| Some(*id) != last_before) {
+            // The file may not exist, so a failure to delete it is expected here
+            let _ = corpus.remove(id);
+        }
+    })
+}
+
+// Inputs the overflow buffer holds at most while corpus writes fail; later ones are dropped
+const MAX_PENDING_INPUTS: usize = 4096;
+
+// Queues an input that could not be written, behind those already waiting
+fn buffer_input(pending: &mut VecDeque<PendingInput>, seen: &mut SeenInputs, input: PendingInput) -> FzilAddVerdict {
+    if pending.len() >= MAX_PENDING_INPUTS {
+        // So it can be added again once writes work
+        seen.remove(&input.bytes);
+        return FzilAddVerdict::Dropped;
+    }
+    pending.push_back(input);
+    FzilAddVerdict::Buffered
+}
+
+// Retries buffered inputs in order, stopping at the first one that still cannot be written.
+// Returns every input that made it to disk, with its id.
+fn flush_to_disk<C: Corpus<Input = BytesInput>>(
+    corpus: &mut C,
+    pending: &mut VecDeque<PendingInput>,
+    seen: &mut SeenInputs,
+) -> Vec<(CorpusId, PendingInput)> {
+    let mut flushed = Vec::new();
+    while let Some(input) = pending.front() {
+        let Ok(id) = try_add_to_disk(corpus, input.testcase()) else {
+            break;
+        };
+        seen.persist(&input.bytes);
+        flushed.extend(pending.pop_front().map(|input| (id, input)));
+    }
+    if !flushed.is_empty() && pending.is_empty() {
+        println!("Corpus writes recovered, overflow buffer flushed");
+    }
+    flushed
+}
+
 
 // Define your wrapper struct
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
@@ -55,116 +268,314 @@ unsafe impl<I: Input + Send + Sync> Sync for FzilOnDiskCorpus<I> {}
 // Concrete type for uniffi
 #[derive(uniffi::Object, Debug)]
 pub struct FzilOnDiskCorpusBytes {
-    inner: Arc<Mutex<FzilOnDiskCorpus<BytesInput>>>,
+    // Read locks are only for calls that never load an input, see MyFzilScheduler::state
+    inner: Arc<RwLock<FzilOnDiskCorpus<BytesInput>>>,
+    // Inputs that could not be written to disk yet, oldest first
+    pending: Mutex<VecDeque<PendingInput>>,
+    seen: Mutex<SeenInputs>,
+    inputs: InputIndex,
 }
 
 // Implementation for FzilOnDiskCorpusBytes
 #[uniffi::export]
 impl FzilOnDiskCorpusBytes {
     #[uniffi::constructor]
-    pub fn new() -> Arc<FzilOnDiskCorpusBytes> {
+    pub fn new() -> Result<Arc<FzilOnDiskCorpusBytes>, FuzzilliBridgeError> {
         let inner_corpus = FzilOnDiskCorpus {
-            inner: OnDiskCorpus::new(PathBuf::from("./pcorpus")).unwrap(),
+            inner: open_disk_corpus(CORPUS_DIR)?,
         };
-        Arc::new(FzilOnDiskCorpusBytes {
-            inner: Arc::new(Mutex::new(inner_corpus)),
-        })
+        let seen = SeenInputs::open(inner_corpus.inner.dir_path(), dedup::CORPUS_SEEN_FILE)?;
+        Ok(Arc::new(FzilOnDiskCorpusBytes {
+            inner: Arc::new(RwLock::new(inner_corpus)),
+            pending: Mutex::new(VecDeque::new()),
+            seen: Mutex::new(seen),
+            inputs: InputIndex::default(),
+        }))
     }
 
-    pub fn add_input(&self, input: Vec<u8>) {
-        let input = BytesInput::new(input);
-        let testcase = Testcase::new(input);
-
-        // Lock the mutex to obtain a mutable reference to the inner corpus
-        if let Ok(mut inner_corpus) = self.inner.lock() {
-            // Now call add on the inner mutable reference
-            inner_corpus.inner.add(testcase).unwrap();
-        } else {
-            // Handle the case where mutex lock fails
-            println!("Unable to obtain mutable reference to inner corpus");
-            // Optionally panic if locking fails
-            // panic!("Mutex lock failed");
+    // Duplicates of inputs already in the corpus (or waiting to be written) are reported
+    // and not stored
+    pub fn add_input(&self, input: Vec<u8>) -> FzilAddVerdict {
+        let input = PendingInput::new(input);
+        let mut inner_corpus = self.inner.write().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        let mut seen = self.seen.lock().unwrap();
+        if !seen.insert(&input.bytes) {
+            return FzilAddVerdict::Duplicate;
+        }
+        // Keep insertion order: older buffered inputs go first
+        flush_to_disk(&mut inner_corpus.inner, &mut pending, &mut seen);
+        if !pending.is_empty() {
+            return buffer_input(&mut pending, &mut seen, input);
+        }
+        match try_add_to_disk(&mut inner_corpus.inner, input.testcase()) {
+            Ok(id) => {
+                seen.persist(&input.bytes);
+                FzilAddVerdict::added(id)
+            }
+            Err(e) => {
+                println!("Corpus write failed, buffering input in memory: {}", e);
+                buffer_input(&mut pending, &mut seen, input)
+            }
         }
     }
 
     pub fn count(&self) -> u64 {
-        let inner_corpus = self.inner.lock().unwrap();
+        let inner_corpus = self.inner.read().unwrap();
         let count = inner_corpus.inner.count();
         count as u64
     }
 
-    pub fn ids(&self)
+    // Number of inputs held in memory because writing them to disk failed
+    pub fn pending_writes(&self) -> u64 {
+        self.pending.lock().unwrap().len() as u64
+    }
+
+    // Retry writing buffered inputs to disk, returns how many are still pending
+    pub fn flush_pending(&self) -> u64 {
+        let mut inner_corpus = self.inner.write().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        flush_to_disk(&mut inner_corpus.inner, &mut pending, &mut self.seen.lock().unwrap());
+        pending.len() as u64
+    }
+
+    pub fn ids(&self) -> Result<(), FuzzilliBridgeError>
     {
-        let inner_corpus = self.inner.lock().unwrap();
+        let inner_corpus = self.inner.read().unwrap();
 
-        let id = inner_corpus.inner.current().map(|id| inner_corpus.inner.next(id)).flatten().unwrap_or_else(|| inner_corpus.inner.first().unwrap());
+        let first = inner_corpus.inner.first().ok_or(FuzzilliBridgeError::EmptyCorpus)?;
+        let last = inner_corpus.inner.last().ok_or(FuzzilliBridgeError::EmptyCorpus)?;
+        let id = inner_corpus.inner.current().and_then(|id| inner_corpus.inner.next(id)).unwrap_or(first);
         println!("Current ID: {}", id);
-        println!("Last ID: {}", inner_corpus.inner.last().expect("Cant get last id"));
-        println!("First ID: {}", inner_corpus.inner.first().expect("cant get first id"));
+        println!("Last ID: {}", last);
+        println!("First ID: {}", first);
+        Ok(())
     }
 
   
-    pub fn first_index(&self) -> u64{
+    pub fn first_index(&self) -> Result<u64, FuzzilliBridgeError> {
         
-        let inner_corpus = self.inner.lock().unwrap();
-        let first_id = inner_corpus.inner.first().unwrap();
+        let inner_corpus = self.inner.read().unwrap();
+        let first_id = inner_corpus.inner.first().ok_or(FuzzilliBridgeError::EmptyCorpus)?;
 
         let first_id_usize : usize = first_id.into();
         let first_id_u64 : u64 = first_id_usize as u64;
-        first_id_u64
+        Ok(first_id_u64)
     }
 
-    pub fn last_index(&self) -> u64{
+    pub fn last_index(&self) -> Result<u64, FuzzilliBridgeError> {
         
-        let inner_corpus = self.inner.lock().unwrap();
-        let last_id = inner_corpus.inner.last().unwrap();
+        let inner_corpus = self.inner.read().unwrap();
+        let last_id = inner_corpus.inner.last().ok_or(FuzzilliBridgeError::EmptyCorpus)?;
 
         let last_id_usize : usize = last_id.into();
         let last_id_u64 : u64 = last_id_usize as u64;
-        last_id_u64
+        Ok(last_id_u64)
     }
 
     pub fn next_free(&self){
-        let inner_corpus = self.inner.lock().unwrap();
+        let inner_corpus = self.inner.read().unwrap();
 
         println!("{}", inner_corpus.inner.peek_free_id());
     }
 
-    pub fn get_element(&self, corpus_id: u64) -> Vec<u8> {
-        let inner_corpus = self.inner.lock().unwrap();
-        let corpus_id = CorpusId::from(corpus_id as usize);
-        match inner_corpus.inner.get(corpus_id) {
-            Ok(testcase) => {
-                if let Some(input) = testcase.borrow().input() {
-                    input.bytes().to_vec()
-                } else {
-                    Vec::new() // Return an empty Vec<u8> if the input is None
-                }
-            }
-            Err(_) => Vec::new(), // Return an empty Vec<u8> if the corpus_id is invalid
+    // Only the first lookup of an entry locks the corpus, see InputIndex
+    pub fn get_element(&self, id: u64) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let corpus_id = CorpusId::from(id as usize);
+        if let Some(bytes) = self.inputs.get(corpus_id) {
+            return Ok(bytes);
         }
+        let inner_corpus = self.inner.write().unwrap();
+        let bytes = corpus_input_bytes(&inner_corpus.inner, corpus_id)?;
+        self.inputs.insert(corpus_id, &inner_corpus.inner.get(corpus_id)?.borrow());
+        Ok(bytes)
     }
     
-    pub fn get_random_element(&self) -> Vec<u8> {
-        let first_index = self.first_index();
-        let last_index = self.last_index();
-        
-        if first_index > last_index {
-            return Vec::new(); // Return an empty Vec<u8> if the range is invalid
-        }
-        
-        let mut rng = rand::thread_rng();
-        let random_index = rng.gen_range(first_index..=last_index);
-        
-        self.get_element(random_index)
+    // Ids can have gaps (a failed write gives its id up), so the pick is among existing ones
+    pub fn get_random_element(&self) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let id = sample_ids(&self.inner.read().unwrap().inner, 1, None)
+            .pop()
+            .ok_or(FuzzilliBridgeError::EmptyCorpus)?;
+        self.get_element(id)
+    }
+
+    // Returns up to `n` corpus ids picked uniformly at random, without replacement.
+    // Only the ids are walked, not the inputs. Entries added here carry no exec time, so
+    // unlike MyFzilScheduler::sample_entries there is nothing to weigh them by.
+    pub fn sample_entries(&self, n: u64) -> Vec<u64> {
+        sample_ids(&self.inner.read().unwrap().inner, n as usize, None)
     }
     
 }
 
+type FzilState = StdState<BytesInput, MainCorpus, StdRand, OnDiskCorpus<BytesInput>>;
+type FzilMutator = StdScheduledMutator<BytesInput, HavocMutationsType<BytesInput>, FzilState>;
+// For when crossover is impossible, see mutate_locked
+type FzilNoCrossoverMutator = StdScheduledMutator<BytesInput, HavocMutationsNoCrossoverType, FzilState>;
+
+// What Fuzzilli reported about a crashing program, stored on its solutions entry
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashMetadata {
+    pub signal: i32,
+    pub stderr: String,
+}
+
+libafl_bolts::impl_serdeany!(CrashMetadata);
+
+// How an execution of a program ended, as seen by Fuzzilli
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct FzilExecStatus {
+    pub exit_code: i32,
+    // Terminating signal, 0 if the process exited normally
+    pub signal: i32,
+    pub timed_out: bool,
+}
+
+impl FzilExecStatus {
+    fn exit_kind(&self) -> ExitKind {
+        if self.timed_out {
+            ExitKind::Timeout
+        } else if self.signal != 0 {
+            ExitKind::Crash
+        } else {
+            ExitKind::Ok
+        }
+    }
+}
+
+// Written next to every exported entry as `<name>.json`
+#[derive(Serialize, Debug)]
+struct ExportSidecar {
+    id: u64,
+    len: u64,
+    exec_time_micros: Option<u64>,
+}
+
+// A crashing program together with the details it was reported with
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct FzilSolution {
+    pub input: Vec<u8>,
+    pub signal: i32,
+    pub stderr: String,
+}
+
+// A scheduled input and the corpus entry it came from
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct FzilSuggestion {
+    pub id: u64,
+    pub bytes: Vec<u8>,
+}
+
+// Writes the new content of an on-disk entry under a name derived from it (keeping the
+// time and origin of descriptive names) together with a fresh .metadata sidecar, then
+// deletes the old files
+fn rewrite_entry_files(testcase: &mut Testcase<BytesInput>, bytes: &[u8]) -> Result<(), FuzzilliBridgeError> {
+    let old_path = testcase.file_path().clone().expect("only called for entries on disk");
+    let dir = old_path.parent().expect("entry files live in the corpus directory");
+    let old_name = testcase.filename().clone().unwrap_or_default();
+    let hash = BytesInput::new(bytes.to_vec()).generate_name(None);
+    let base = match old_name.rsplit_once(",hash:") {
+        Some((prefix, _)) => format!("{},hash:{}", prefix, hash),
+        None => hash,
+    };
+    // Claim the name through its lock file like LibAFL does, another entry may have it
+    let mut name = base.clone();
+    let mut ctr = 2;
+    while name != old_name
+        && std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(format!(".{}.lafl_lock", name)))
+            .is_err()
+    {
+        name = format!("{}-{}", base, ctr);
+        ctr += 1;
+    }
+
+    let path = dir.join(&name);
+    write_file_atomic(&path, bytes)?;
+    let metadata_path = dir.join(format!(".{}.metadata", name));
+    let sidecar = OnDiskMetadata {
+        metadata: testcase.metadata_map(),
+        exec_time: testcase.exec_time(),
+        executions: testcase.executions(),
+    };
+    let sidecar = serde_json::to_vec_pretty(&sidecar).map_err(|e| FuzzilliBridgeError::Io { reason: e.to_string() })?;
+    write_file_atomic(&metadata_path, &sidecar)?;
+    if name != old_name {
+        // Nothing refers to the old files anymore, a leftover is harmless
+        for old_file in [old_name.clone(), format!(".{}.metadata", old_name), format!(".{}.lafl_lock", old_name)] {
+            let _ = std::fs::remove_file(dir.join(old_file));
+        }
+    }
+    *testcase.filename_mut() = Some(name);
+    *testcase.file_path_mut() = Some(path);
+    *testcase.metadata_path_mut() = Some(metadata_path);
+    Ok(())
+}
+
+// Ids of the enabled corpus entries, in order
+fn corpus_ids(state: &FzilState) -> Vec<CorpusId> {
+    let mut ids = Vec::new();
+    let mut current = state.corpus().first();
+    while let Some(id) = current {
+        ids.push(id);
+        current = state.corpus().next(id);
+    }
+    ids
+}
+
+// Loads the input of a corpus entry (from disk if it is not cached) and copies it out
+fn input_bytes(state: &FzilState, id: CorpusId) -> Result<Vec<u8>, FuzzilliBridgeError> {
+    corpus_input_bytes(state.corpus(), id)
+}
+
+fn corpus_input_bytes<C: Corpus<Input = BytesInput>>(corpus: &C, id: CorpusId) -> Result<Vec<u8>, FuzzilliBridgeError> {
+    let id_usize: usize = id.into();
+    let testcase = corpus
+        .get(id)
+        .map_err(|_| FuzzilliBridgeError::UnknownId { id: id_usize as u64 })?;
+    let testcase_borrowed = testcase.borrow();  // Borrow the testcase
+    let input = testcase_borrowed
+        .input()
+        .as_ref()
+        .ok_or(FuzzilliBridgeError::UnknownId { id: id_usize as u64 })?;
+    Ok(input.bytes().to_vec())
+}
+
 #[derive(uniffi::Object, Debug)]
 pub struct MyFzilScheduler {
-    inner: Arc<Mutex<QueueScheduler<StdState<BytesInput, OnDiskCorpus<BytesInput>, StdRand, OnDiskCorpus<BytesInput>>>>>,
-    state: Arc<Mutex<StdState<BytesInput, OnDiskCorpus<BytesInput>, StdRand, OnDiskCorpus<BytesInput>>>>,
+    inner: Arc<Mutex<QueueScheduler<FzilState>>>,
+    // Loading an input goes through the corpus cache (a RefCell), so only calls that never
+    // load one (counts, stats) may share the lock; everything else takes it for writing.
+    // get_entry reads inputs through `inputs` instead.
+    state: Arc<RwLock<FzilState>>,
+    event_log: Mutex<Option<FzilEventLog>>,
+    // Inputs that could not be written to disk yet, oldest first
+    pending: Mutex<VecDeque<PendingInput>>,
+    change_log: Mutex<ChangeLog>,
+    seen: Mutex<SeenInputs>,
+    // Kept in step with the corpus by remove_element, replace_element and restore_state
+    inputs: InputIndex,
+    // Exec times of the entries that have one, so sample_entries can weigh entries without
+    // loading them. Filled on add, kept in step like `inputs` (replacing keeps the time).
+    exec_times: Mutex<HashMap<CorpusId, Duration>>,
+    stats: Mutex<StatsTracker>,
+    sync: Mutex<Option<SyncDir>>,
+    maintenance: Mutex<Option<MaintenanceWindow>>,
+    mutator: Mutex<FzilMutator>,
+    no_crossover_mutator: Mutex<FzilNoCrossoverMutator>,
+    validate_programs: Mutex<bool>,
+    listener: Mutex<Option<ListenerHandle>>,
+    descriptive_names: Mutex<bool>,
+    // Injected seeds served ahead of the queue, with how many schedules each has left
+    priority: Mutex<PriorityInbox>,
+}
+
+#[derive(Debug)]
+struct PriorityInbox {
+    entries: VecDeque<(CorpusId, u64)>,
+    schedules_per_entry: u64,
 }
 
 unsafe impl Send for MyFzilScheduler {}
@@ -174,57 +585,772 @@ unsafe impl Sync for MyFzilScheduler {}
 impl MyFzilScheduler {
     // Constructor to create a new QueueScheduler with StdState
     #[uniffi::constructor]
-    pub fn new() -> Arc<MyFzilScheduler> {
+    pub fn new() -> Result<Arc<MyFzilScheduler>, FuzzilliBridgeError> {
+        Self::with_backend(FzilCorpusBackend::OnDisk)
+    }
+
+    // Like new(), with the main corpus held by `backend` instead of OnDiskCorpus
+    #[uniffi::constructor]
+    #[allow(clippy::arc_with_non_send_sync)] // Send/Sync are asserted on MyFzilScheduler above
+    pub fn with_backend(backend: FzilCorpusBackend) -> Result<Arc<MyFzilScheduler>, FuzzilliBridgeError> {
         let rand = StdRand::with_seed(current_nanos());
-        let corpus1 = OnDiskCorpus::new(PathBuf::from("./pcorpus")).unwrap();
-        let corpus2 = OnDiskCorpus::new(PathBuf::from("./ocorpus")).unwrap();
-        
+        let corpus1 = match backend {
+            FzilCorpusBackend::InMemory => MainCorpus::InMemory(InMemoryCorpus::new()),
+            FzilCorpusBackend::OnDisk => MainCorpus::OnDisk(open_disk_corpus(CORPUS_DIR)?),
+            FzilCorpusBackend::CachedOnDisk { cache_size } => MainCorpus::CachedOnDisk(
+                CachedOnDiskCorpus::new(PathBuf::from(CORPUS_DIR), cache_size.max(1) as usize).map_err(|e| {
+                    FuzzilliBridgeError::CorpusDir {
+                        path: CORPUS_DIR.to_string(),
+                        reason: e.to_string(),
+                    }
+                })?,
+            ),
+        };
+        let corpus2 = open_disk_corpus("./ocorpus")?;
+
         let state = StdState::new(
             rand,
             corpus1,
             corpus2,
             &mut ConstFeedback::new(false),
             &mut ConstFeedback::new(false),
-        ).unwrap();
+        )?;
 
+        let seen = match backend {
+            FzilCorpusBackend::InMemory => SeenInputs::in_memory(),
+            _ => SeenInputs::open(Path::new(CORPUS_DIR), dedup::SEEN_FILE)?,
+        };
         let scheduler = QueueScheduler::new();
 
-        Arc::new(MyFzilScheduler {
+        Ok(Arc::new(MyFzilScheduler {
             inner: Arc::new(Mutex::new(scheduler)),
-            state: Arc::new(Mutex::new(state)),
+            state: Arc::new(RwLock::new(state)),
+            event_log: Mutex::new(None),
+            pending: Mutex::new(VecDeque::new()),
+            change_log: Mutex::new(ChangeLog::default()),
+            seen: Mutex::new(seen),
+            inputs: InputIndex::default(),
+            exec_times: Mutex::new(HashMap::new()),
+            stats: Mutex::new(StatsTracker::default()),
+            sync: Mutex::new(None),
+            maintenance: Mutex::new(None),
+            mutator: Mutex::new(StdScheduledMutator::new(havoc_mutations())),
+            no_crossover_mutator: Mutex::new(StdScheduledMutator::new(havoc_mutations_no_crossover())),
+            validate_programs: Mutex::new(false),
+            listener: Mutex::new(None),
+            descriptive_names: Mutex::new(false),
+            priority: Mutex::new(PriorityInbox {
+                entries: VecDeque::new(),
+                schedules_per_entry: 1,
+            }),
+        }))
+    }
+
+    // Mirror every add/suggestion as a JSON line to `target` (a file path or `tcp://host:port`).
+    // File logs rotate to `<target>.1` past `rotate_bytes` (0 disables rotation). Socket
+    // targets are written from a background thread; if the collector falls too far behind,
+    // events are dropped rather than stalling the fuzzer. An empty target turns mirroring off.
+    pub fn set_event_log(&self, target: String, rotate_bytes: u64) -> Result<(), FuzzilliBridgeError> {
+        // Connecting can take a while, so it happens before the log is locked
+        let opened = (!target.is_empty()).then(|| FzilEventLog::open(&target, rotate_bytes));
+        let mut event_log = self.event_log.lock().unwrap();
+        *event_log = None;
+        if let Some(opened) = opened {
+            *event_log = Some(opened?);
+        }
+        Ok(())
+    }
+
+    // Have `listener` called for every corpus add and solution, and when corpus writes fail
+    // or recover, from a separate thread and without any bridge lock held. Replaces any
+    // previous listener.
+    pub fn set_listener(&self, listener: Box<dyn FzilListener>) {
+        *self.listener.lock().unwrap() = Some(ListenerHandle::spawn(listener));
+    }
+
+    pub fn clear_listener(&self) {
+        *self.listener.lock().unwrap() = None;
+    }
+
+    // Add an input to the corpus. If the disk write fails the input is kept in memory and
+    // retried on the next add or flush_pending(), instead of aborting the host.
+    // Duplicates of inputs already in the corpus (or waiting to be written) are reported
+    // and not stored. After restore_state that is the restored corpus.
+    pub fn add_input(&self, input_data: Vec<u8>) -> FzilAddVerdict {
+        self.add_pending_input(PendingInput::new(input_data))
+    }
+
+    // Like add_input, but records how long Fuzzilli took to execute the program so that
+    // time-aware schedulers can use it (also persisted in the entry's .metadata file)
+    pub fn add_input_with_exec_time(&self, input_data: Vec<u8>, exec_time_micros: u64) -> FzilAddVerdict {
+        self.add_pending_input(PendingInput {
+            exec_time: Some(Duration::from_micros(exec_time_micros)),
+            ..PendingInput::new(input_data)
         })
     }
 
-    // Add an input to the corpus
-    pub fn add_input(&self, input_data: Vec<u8>) {
-        let input = BytesInput::new(input_data);
-        let testcase = Testcase::new(input);
-        let mut state = self.state.lock().unwrap();
-        state.corpus_mut().add(testcase).unwrap();
+    // Add many inputs at once (e.g. a seed corpus import), taking the locks a single time.
+    // Returns one verdict per input, in order.
+    pub fn add_inputs(&self, inputs: Vec<Vec<u8>>) -> Vec<FzilAddVerdict> {
+        let mut state = self.state.write().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        let mut seen = self.seen.lock().unwrap();
+        self.flush_pending_locked(&mut state, &mut pending, &mut seen);
+        inputs
+            .into_iter()
+            .map(|bytes| {
+                if let Some(rejected) = self.validate(&bytes) {
+                    return rejected;
+                }
+                if !seen.insert(&bytes) {
+                    self.stats.lock().unwrap().record_duplicate();
+                    return FzilAddVerdict::Duplicate;
+                }
+                self.store_locked(&mut state, &mut pending, &mut seen, PendingInput::new(bytes))
+            })
+            .collect()
+    }
+
+    // Name new corpus files `time:<ms>,origin:<local|imported>,hash:<content hash>` instead of
+    // just the content hash, for easier triage. Origin and discovery time are recorded in the
+    // entry's .metadata sidecar either way. Existing files keep their names.
+    pub fn set_descriptive_names(&self, enabled: bool) {
+        *self.descriptive_names.lock().unwrap() = enabled;
+    }
+
+    // Reject inputs that do not decode as a serialized FuzzIL program on every add path
+    // (including sync imports), so corrupt programs never reach the corpus. Off by default.
+    pub fn set_validate_programs(&self, enabled: bool) {
+        *self.validate_programs.lock().unwrap() = enabled;
+    }
+
+    // Size of a corpus entry in FuzzIL instructions rather than bytes
+    pub fn program_length(&self, id: u64) -> Result<u64, FuzzilliBridgeError> {
+        let state = self.state.write().unwrap();
+        let bytes = input_bytes(&state, CorpusId::from(id as usize))?;
+        let program = fuzzil::decode_program(&bytes).map_err(|reason| FuzzilliBridgeError::InvalidProgram { id, reason })?;
+        Ok(program.instructions as u64)
+    }
+
+    // Apply `rounds` rounds of LibAFL's havoc mutations (stacked byte-level mutations, plus
+    // splicing with corpus entries when there are any) to `input`, using the state RNG
+    pub fn mutate(&self, input: Vec<u8>, rounds: u64) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let mut state = self.state.write().unwrap();
+        self.mutate_locked(&mut state, input, rounds)
+    }
+
+    // One havoc round on a copy of a corpus entry; the entry itself is left untouched
+    pub fn mutate_corpus_entry(&self, id: u64) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let mut state = self.state.write().unwrap();
+        let input = input_bytes(&state, CorpusId::from(id as usize))?;
+        self.mutate_locked(&mut state, input, 1)
+    }
+
+    // Add an input that next_input serves before anything from the queue, for the configured
+    // number of schedules (see set_priority_schedules). Meant for known-interesting programs
+    // injected mid-campaign. Duplicates and buffered inputs are not prioritized.
+    pub fn add_priority_input(&self, input_data: Vec<u8>) -> FzilAddVerdict {
+        let verdict = self.add_pending_input(PendingInput::new(input_data));
+        if let FzilAddVerdict::Added { id } = verdict {
+            let mut priority = self.priority.lock().unwrap();
+            let schedules = priority.schedules_per_entry;
+            if schedules > 0 {
+                priority.entries.push_back((CorpusId::from(id as usize), schedules));
+            }
+        }
+        verdict
+    }
+
+    // How many times each priority input is scheduled ahead of the queue (default 1)
+    pub fn set_priority_schedules(&self, schedules: u64) {
+        self.priority.lock().unwrap().schedules_per_entry = schedules;
+    }
+
+    // Remove an entry from the corpus (including its files) and from every structure that
+    // refers to it. The queue continues with the entry that followed it.
+    pub fn remove_element(&self, id: u64) -> Result<(), FuzzilliBridgeError> {
+        let corpus_id = CorpusId::from(id as usize);
+        let mut scheduler = self.inner.lock().unwrap();
+        let mut state = self.state.write().unwrap();
+        if state.corpus().get(corpus_id).is_err() {
+            return Err(FuzzilliBridgeError::UnknownId { id });
+        }
+        // The scheduler must never be left pointing at a removed entry
+        if *state.corpus().current() == Some(corpus_id) {
+            *state.corpus_mut().current_mut() = state.corpus().prev(corpus_id);
+        }
+        // May fail if the entry file is already gone, which must not prevent removing it
+        let bytes = input_bytes(&state, corpus_id).ok();
+        let testcase = match state.corpus_mut().remove(corpus_id) {
+            Ok(testcase) => Some(testcase),
+            // The entry is already out of the index at this point, only deleting its files failed
+            Err(e) => {
+                println!("Unable to delete files of corpus entry {}: {}", id, e);
+                None
+            }
+        };
+        scheduler.on_remove(&mut *state, corpus_id, &testcase)?;
+        self.inputs.remove(corpus_id);
+        self.exec_times.lock().unwrap().remove(&corpus_id);
+        // Allow the input to be added again later
+        if let Some(bytes) = bytes {
+            self.seen.lock().unwrap().remove(&bytes);
+        }
+
+        self.priority.lock().unwrap().entries.retain(|(priority_id, _)| *priority_id != corpus_id);
+        self.change_log.lock().unwrap().removed(corpus_id);
+        self.log_event(FzilEventKind::Remove, corpus_id, 0);
+        Ok(())
+    }
+
+    // Replace the content of an entry, keeping its id, queue position, metadata (origin and
+    // discovery time) and exec time. Files on disk are renamed after the new content.
+    pub fn replace_element(&self, id: u64, input_data: Vec<u8>) -> Result<(), FuzzilliBridgeError> {
+        let corpus_id = CorpusId::from(id as usize);
+        let mut scheduler = self.inner.lock().unwrap();
+        let mut state = self.state.write().unwrap();
+        let len = input_data.len();
+        let old_bytes = input_bytes(&state, corpus_id)?;
+        // Two entries holding the same content would share one hash, which removing either
+        // of them then drops
+        let mut seen = self.seen.lock().unwrap();
+        if SeenInputs::hash(&input_data) != SeenInputs::hash(&old_bytes) && seen.contains(&input_data) {
+            return Err(FuzzilliBridgeError::DuplicateInput { id });
+        }
+        // Swap the testcase in place instead of going through Corpus::replace, which leaves
+        // the cached corpus pointing at an entry without input that it never reloads
+        let prev = {
+            let mut testcase = state.corpus().get(corpus_id)?.borrow_mut();
+            let mut replacement = testcase.clone();
+            replacement.set_input(BytesInput::new(input_data.clone()));
+            // In-memory entries have no files
+            if replacement.file_path().is_some() {
+                rewrite_entry_files(&mut replacement, &input_data)?;
+            }
+            core::mem::replace(&mut *testcase, replacement)
+        };
+        self.inputs.remove(corpus_id);
+        seen.remove(&old_bytes);
+        seen.insert(&input_data);
+        seen.persist(&input_data);
+        drop(seen);
+        scheduler.on_replace(&mut *state, corpus_id, &prev)?;
+
+        self.change_log.lock().unwrap().replaced(corpus_id);
+        self.log_event(FzilEventKind::Replace, corpus_id, len);
+        Ok(())
+    }
+
+    // Objective check for an executed program: like LibAFL's CrashFeedback, only executions
+    // killed by a signal count (timeouts and non-zero exit codes do not). Crashing inputs go
+    // to the solutions corpus as with add_crash; returns the solution id, or None otherwise.
+    pub fn report_execution(&self, input: Vec<u8>, status: FzilExecStatus) -> Result<Option<u64>, FuzzilliBridgeError> {
+        if status.exit_kind() != ExitKind::Crash {
+            return Ok(None);
+        }
+        self.add_crash(input, status.signal, String::new()).map(Some)
+    }
+
+    // Persist a crashing program in the solutions corpus (./ocorpus), separately from the
+    // main corpus. Returns the solution id.
+    pub fn add_crash(&self, input: Vec<u8>, signal: i32, stderr: String) -> Result<u64, FuzzilliBridgeError> {
+        let len = input.len();
+        let mut testcase = Testcase::new(BytesInput::new(input));
+        testcase.add_metadata(CrashMetadata { signal, stderr });
+
+        let mut state = self.state.write().unwrap();
+        let id = try_add_to_disk(state.solutions_mut(), testcase)?;
+        self.log_event(FzilEventKind::Crash, id, len);
+        let id_usize: usize = id.into();
+        self.notify(Notification::Solution { id: id_usize as u64, signal });
+        Ok(id_usize as u64)
+    }
+
+    pub fn solutions_count(&self) -> u64 {
+        let state = self.state.read().unwrap();
+        state.solutions().count() as u64
+    }
+
+    pub fn get_solution(&self, id: u64) -> Result<FzilSolution, FuzzilliBridgeError> {
+        let state = self.state.write().unwrap();
+        let testcase = state
+            .solutions()
+            .get(CorpusId::from(id as usize))
+            .map_err(|_| FuzzilliBridgeError::UnknownId { id })?;
+        let testcase_borrowed = testcase.borrow();
+        let input = testcase_borrowed
+            .input()
+            .as_ref()
+            .ok_or(FuzzilliBridgeError::UnknownId { id })?;
+        let crash = testcase_borrowed.metadata::<CrashMetadata>()?;
+        Ok(FzilSolution {
+            input: input.bytes().to_vec(),
+            signal: crash.signal,
+            stderr: crash.stderr.clone(),
+        })
+    }
+
+    // Logged events with a timestamp (ms since the epoch) at or after `ts_ms`, oldest first.
+    // Empty when no file event log is configured.
+    pub fn events_since(&self, ts_ms: u64) -> Vec<FzilEvent> {
+        // Reading the files can take a while, so it happens after the log is unlocked
+        let path = self.event_log.lock().unwrap().as_mut().and_then(FzilEventLog::flushed_path);
+        match path {
+            Some(path) => events::events_since(&path, ts_ms),
+            None => Vec::new(),
+        }
+    }
+
+    // Corpus entries added, replaced or removed after `version` (0 for everything), plus the
+    // current version. Inputs still waiting in the overflow buffer are reported once they
+    // reach the corpus.
+    pub fn changes_since(&self, version: u64) -> FzilChangeSet {
+        self.change_log.lock().unwrap().since(version as usize)
+    }
+
+    // Snapshot the state to `path`: the corpus and solutions index with per-entry metadata,
+    // the scheduler position, the RNG and state metadata. Inputs themselves stay in the
+    // corpus directories, except with the in-memory backend where they are part of the
+    // snapshot. Inputs still in the overflow buffer are not part of the snapshot.
+    pub fn save_state(&self, path: String) -> Result<(), FuzzilliBridgeError> {
+        self.write_snapshot(&path)?;
+        Ok(())
+    }
+
+    // Replace the current state with a snapshot written by save_state, resuming the campaign
+    // where it left off. changes_since() reports the swap as changes: entries only in the old
+    // corpus as removed, ids in both as replaced and the rest as added.
+    pub fn restore_state(&self, path: String) -> Result<(), FuzzilliBridgeError> {
+        let serialized = std::fs::read(&path)?;
+        let restored: FzilState = postcard::from_bytes(&serialized).map_err(|e| FuzzilliBridgeError::InvalidSnapshot {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+
+        // Everything that can fail happens before the current state is touched: the restored
+        // inputs are read up front, and are needed to dedup against afterwards anyway
+        let after = corpus_ids(&restored);
+        let mut stored = Vec::new();
+        let mut exec_times = HashMap::new();
+        for id in &after {
+            let bytes = input_bytes(&restored, *id).map_err(|_| FuzzilliBridgeError::InvalidSnapshot {
+                path: path.clone(),
+                reason: format!("the input of entry {} cannot be loaded", usize::from(*id)),
+            })?;
+            stored.push(bytes);
+            // Loaded by input_bytes already, this does not touch the disk again
+            if let Some(exec_time) = *restored.corpus().get(*id)?.borrow().exec_time() {
+                exec_times.insert(*id, exec_time);
+            }
+        }
+
+        let mut state = self.state.write().unwrap();
+        let before = corpus_ids(&state);
+        *state = restored;
+        self.inputs.clear();
+        *self.exec_times.lock().unwrap() = exec_times;
+        // The version keeps counting up, so mirrors see the swap as ordinary changes
+        self.change_log.lock().unwrap().swapped(&before, &after);
+        // Dedup against what is in the corpus now, plus inputs still waiting to be written
+        let pending = self.pending.lock().unwrap();
+        self.seen.lock().unwrap().reset(
+            stored.iter().map(Vec::as_slice),
+            pending.iter().map(|input| input.bytes.as_slice()),
+        );
+        self.priority.lock().unwrap().entries.clear();
+        Ok(())
+    }
+
+    // Exchange corpus entries with sibling instances through `sync_dir`: our entries go to
+    // `<sync_dir>/<instance>/queue`, and files in the other instances' queues are added
+    // locally unless already present. Runs every `interval_secs` from next_input (0 only
+    // syncs on sync_now). An empty `sync_dir` turns syncing off.
+    pub fn enable_sync(&self, sync_dir: String, instance: String, interval_secs: u64) -> Result<(), FuzzilliBridgeError> {
+        let mut sync = self.sync.lock().unwrap();
+        *sync = None;
+        if !sync_dir.is_empty() {
+            *sync = Some(SyncDir::new(&sync_dir, &instance, Duration::from_secs(interval_secs))?);
+        }
+        Ok(())
+    }
+
+    pub fn sync_now(&self) -> Result<FzilSyncReport, FuzzilliBridgeError> {
+        let mut sync = self.sync.lock().unwrap();
+        match sync.as_mut() {
+            Some(sync) => self.sync_locked(sync),
+            None => Ok(FzilSyncReport::default()),
+        }
+    }
+
+    // Run flush_pending, sync_now, a checkpoint to `checkpoint_path` (with the stats next to it
+    // as `<checkpoint_path>.stats.json`) and get_stats in one pass every `interval_secs`, from
+    // next_input. An empty `checkpoint_path` skips checkpointing, 0 only runs on run_maintenance.
+    pub fn enable_maintenance(&self, interval_secs: u64, checkpoint_path: String) {
+        let checkpoint_path = Some(PathBuf::from(checkpoint_path)).filter(|p| !p.as_os_str().is_empty());
+        *self.maintenance.lock().unwrap() = Some(MaintenanceWindow::new(Duration::from_secs(interval_secs), checkpoint_path));
+    }
+
+    pub fn run_maintenance(&self) -> Result<FzilMaintenanceReport, FuzzilliBridgeError> {
+        let checkpoint_path = match self.maintenance.lock().unwrap().as_mut() {
+            Some(window) => {
+                window.mark_run();
+                window.checkpoint_path.clone()
+            }
+            None => None,
+        };
+
+        let pending_writes = self.flush_pending();
+        let sync = match self.sync.lock().unwrap().as_mut() {
+            Some(sync) => Some(self.sync_locked(sync)?),
+            None => None,
+        };
+        if let Some(path) = &checkpoint_path {
+            let len = self.write_snapshot(&path.to_string_lossy())?;
+            let version = self.change_log.lock().unwrap().version();
+            if let Some(log) = self.event_log.lock().unwrap().as_mut() {
+                log.record_checkpoint(version as u64, len);
+            }
+        }
+        let stats = self.get_stats();
+        if let Some(path) = &checkpoint_path {
+            let mut stats_path = path.as_os_str().to_owned();
+            stats_path.push(".stats.json");
+            let report = serde_json::to_vec_pretty(&stats).expect("stats always serialize");
+            write_file_atomic(PathBuf::from(stats_path), &report)?;
+        }
+        Ok(FzilMaintenanceReport {
+            pending_writes,
+            sync,
+            checkpointed: checkpoint_path.is_some(),
+            stats,
+        })
+    }
+
+    // Write the given corpus entries to `dir` as `id_NNNNNN`, each with an `id_NNNNNN.json`
+    // sidecar holding its metadata. Stops at the first unknown id. Returns how many were written.
+    pub fn export_entries(&self, ids: Vec<u64>, dir: String) -> Result<u64, FuzzilliBridgeError> {
+        let dir = PathBuf::from(dir);
+        std::fs::create_dir_all(&dir)?;
+        let state = self.state.write().unwrap();
+        for id in &ids {
+            let corpus_id = CorpusId::from(*id as usize);
+            let bytes = input_bytes(&state, corpus_id)?;
+            let exec_time = *state.corpus().get(corpus_id)?.borrow().exec_time();
+            let sidecar = ExportSidecar {
+                id: *id,
+                len: bytes.len() as u64,
+                exec_time_micros: exec_time.map(|t| t.as_micros() as u64),
+            };
+            let name = entry_file_name(corpus_id);
+            write_file_atomic(dir.join(&name), &bytes)?;
+            let sidecar = serde_json::to_vec_pretty(&sidecar).expect("sidecars always serialize");
+            write_file_atomic(dir.join(format!("{}.json", name)), &sidecar)?;
+        }
+        Ok(ids.len() as u64)
+    }
+
+    // Up to `n` corpus ids picked at random without replacement, e.g. for spot checks or a
+    // host-side splicing pool. With `weighted`, an entry's chance is proportional to
+    // 1 / exec time (see add_input_with_exec_time), and entries without an exec time weigh as
+    // much as the average timed one. When no entry has an exec time, the pick is uniform.
+    pub fn sample_entries(&self, n: u64, weighted: bool) -> Vec<u64> {
+        let state = self.state.read().unwrap();
+        if weighted {
+            sample_ids(state.corpus(), n as usize, Some(&self.exec_times.lock().unwrap()))
+        } else {
+            sample_ids(state.corpus(), n as usize, None)
+        }
+    }
+
+    // Counters for this session (corpus and solution sizes are current totals)
+    pub fn get_stats(&self) -> FzilStats {
+        let state = self.state.read().unwrap();
+        let mut stats = self.stats.lock().unwrap().snapshot();
+        stats.corpus_size = state.corpus().count() as u64;
+        stats.solutions_count = state.solutions().count() as u64;
+        stats.pending_writes = self.pending.lock().unwrap().len() as u64;
+        stats
+    }
+
+    // Number of inputs held in memory because writing them to disk failed
+    pub fn pending_writes(&self) -> u64 {
+        self.pending.lock().unwrap().len() as u64
+    }
+
+    // Retry writing buffered inputs to disk, returns how many are still pending
+    pub fn flush_pending(&self) -> u64 {
+        let mut state = self.state.write().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        self.flush_pending_locked(&mut state, &mut pending, &mut self.seen.lock().unwrap());
+        pending.len() as u64
+    }
+
+    // The input of a corpus entry, without scheduling it. Only the first lookup of an entry
+    // locks the state, unless it is held in memory and the index is full, see InputIndex.
+    pub fn get_entry(&self, id: u64) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let corpus_id = CorpusId::from(id as usize);
+        if let Some(bytes) = self.inputs.get(corpus_id) {
+            return Ok(bytes);
+        }
+        let state = self.state.write().unwrap();
+        let bytes = input_bytes(&state, corpus_id)?;
+        self.inputs.insert(corpus_id, &state.corpus().get(corpus_id)?.borrow());
+        Ok(bytes)
+    }
+
+    // Serve read-only status pages on `addr` (e.g. `127.0.0.1:8080`) from a background thread:
+    // `/stats` (get_stats as JSON) and `/corpus/<id>` (the raw input). Binding errors are
+    // returned here; the server runs until the process exits.
+    pub fn serve_http(self: Arc<Self>, addr: String) -> Result<(), FuzzilliBridgeError> {
+        let listener = std::net::TcpListener::bind(&addr)?;
+        std::thread::spawn(move || http::serve(listener, self));
+        Ok(())
     }
 
     // Get the current test case in the scheduler, returns Vec<u8>
-    pub fn current_testcase(&self) -> Vec<u8> {
-        let state = self.state.lock().unwrap();
-        let current_id = state.corpus().current().unwrap();
+    pub fn current_testcase(&self) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let state = self.state.write().unwrap();
+        let current_id = state.corpus().current().ok_or(FuzzilliBridgeError::EmptyCorpus)?;
 
         // Retrieve the testcase from the corpus using current_id
-        let testcase = state.corpus().get(current_id).unwrap();
-        let testcase_borrowed = testcase.borrow();  // Borrow the testcase
-        let input = testcase_borrowed.input().as_ref().unwrap();
-        input.bytes().to_vec()  // Return as Vec<u8>
+        input_bytes(&state, current_id)
     }
 
     // Get the next input from the scheduler, returns Vec<u8>
-    pub fn next_input(&self) -> Vec<u8> {
+    pub fn next_input(&self) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        Ok(self.suggest_next()?.bytes)
+    }
+
+    // Like next_input, but also says which corpus entry was scheduled so later reports
+    // (crashes, replace_element, ...) can refer to it
+    pub fn suggest_next(&self) -> Result<FzilSuggestion, FuzzilliBridgeError> {
+        self.sync_if_due();
+        self.maintenance_if_due();
         let mut scheduler = self.inner.lock().unwrap();
-        let mut state = self.state.lock().unwrap();
-        let next_id = scheduler.next(&mut *state).unwrap();
+        let mut state = self.state.write().unwrap();
+        if state.corpus().count() == 0 {
+            return Err(FuzzilliBridgeError::EmptyCorpus);
+        }
+        let next_id = match self.next_priority() {
+            Some(id) => id,
+            None => scheduler.next(&mut *state)?,
+        };
+
+        let bytes = input_bytes(&state, next_id)?;
+        self.stats.lock().unwrap().record_suggestion(next_id);
+        self.log_event(FzilEventKind::Suggest, next_id, bytes.len());
+        let id: usize = next_id.into();
+        Ok(FzilSuggestion { id: id as u64, bytes })
+    }
+}
+
+impl MyFzilScheduler {
+    fn add_pending_input(&self, input: PendingInput) -> FzilAddVerdict {
+        if let Some(rejected) = self.validate(&input.bytes) {
+            return rejected;
+        }
+        let mut state = self.state.write().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        let mut seen = self.seen.lock().unwrap();
+        if !seen.insert(&input.bytes) {
+            self.stats.lock().unwrap().record_duplicate();
+            return FzilAddVerdict::Duplicate;
+        }
+        self.flush_pending_locked(&mut state, &mut pending, &mut seen);
+        self.store_locked(&mut state, &mut pending, &mut seen, input)
+    }
+
+    // Buffers behind earlier pending inputs so the corpus keeps the order inputs arrived in.
+    // The input's hash is persisted once it is in the corpus.
+    fn store_locked(
+        &self,
+        state: &mut FzilState,
+        pending: &mut VecDeque<PendingInput>,
+        seen: &mut SeenInputs,
+        mut input: PendingInput,
+    ) -> FzilAddVerdict {
+        if *self.descriptive_names.lock().unwrap() {
+            input.file_name = Some(input.descriptive_name());
+        }
+        if !pending.is_empty() {
+            return buffer_input(pending, seen, input);
+        }
+        match try_add_to_disk(state.corpus_mut(), input.testcase()) {
+            Ok(id) => {
+                seen.persist(&input.bytes);
+                self.on_added(id, &input);
+                FzilAddVerdict::added(id)
+            }
+            Err(e) => {
+                println!("Corpus write failed, buffering input in memory: {}", e);
+                let verdict = buffer_input(pending, seen, input);
+                self.on_write_health(true, pending.len());
+                verdict
+            }
+        }
+    }
+
+    fn mutate_locked(&self, state: &mut FzilState, input: Vec<u8>, rounds: u64) -> Result<Vec<u8>, FuzzilliBridgeError> {
+        let mut input = BytesInput::new(input);
+        for _ in 0..rounds {
+            // LibAFL's crossover mutators call rand.below(0), and so panic, when the corpus
+            // is empty (picking the entry to splice with) or the input is (picking where to
+            // insert). The input can become empty between rounds, so this is checked every time.
+            if state.corpus().count_all() == 0 || input.bytes().is_empty() {
+                self.no_crossover_mutator.lock().unwrap().mutate(state, &mut input)?;
+            } else {
+                self.mutator.lock().unwrap().mutate(state, &mut input)?;
+            }
+        }
+        Ok(input.bytes().to_vec())
+    }
+
+    fn flush_pending_locked(&self, state: &mut FzilState, pending: &mut VecDeque<PendingInput>, seen: &mut SeenInputs) {
+        let flushed = flush_to_disk(state.corpus_mut(), pending, seen);
+        let recovered = !flushed.is_empty() && pending.is_empty();
+        for (id, input) in flushed {
+            self.on_added(id, &input);
+        }
+        if recovered {
+            self.on_write_health(false, 0);
+        }
+    }
+
+    // Priority entries are served round-robin until each has used up its schedules.
+    // The queue position is left alone so normal scheduling resumes where it was.
+    fn next_priority(&self) -> Option<CorpusId> {
+        let mut priority = self.priority.lock().unwrap();
+        let (id, remaining) = priority.entries.pop_front()?;
+        if remaining > 1 {
+            priority.entries.push_back((id, remaining - 1));
+        }
+        Some(id)
+    }
+
+    fn sync_if_due(&self) {
+        let mut sync = self.sync.lock().unwrap();
+        if let Some(sync) = sync.as_mut().filter(|sync| sync.is_due()) {
+            if let Err(e) = self.sync_locked(sync) {
+                println!("Corpus sync failed: {}", e);
+            }
+        }
+    }
+
+    fn maintenance_if_due(&self) {
+        let due = self.maintenance.lock().unwrap().as_ref().is_some_and(|window| window.is_due());
+        if due {
+            if let Err(e) = self.run_maintenance() {
+                println!("Maintenance failed: {}", e);
+            }
+        }
+    }
+
+    fn sync_locked(&self, sync: &mut SyncDir) -> Result<FzilSyncReport, FuzzilliBridgeError> {
+        let mut report = FzilSyncReport::default();
+        sync.mark_synced();
+
+        // Entries imported from siblings are remembered in imported_ids and never exported
+        {
+            let state = self.state.write().unwrap();
+            let change_log = self.change_log.lock().unwrap();
+            for id in change_log.added_since(sync.exported_version) {
+                if sync.imported_ids.contains(&id) {
+                    continue;
+                }
+                // Skip entries removed since they were added
+                let Ok(bytes) = input_bytes(&state, id) else {
+                    continue;
+                };
+                sync.export(&bytes)?;
+                report.exported += 1;
+            }
+            sync.exported_version = change_log.version();
+        }
+
+        for path in sync.new_sibling_files()? {
+            let bytes = std::fs::read(&path)?;
+            let verdict = self.add_pending_input(PendingInput::imported(bytes));
+            // Picked up again on the next sync
+            if verdict == FzilAddVerdict::Dropped {
+                continue;
+            }
+            sync.mark_seen(path);
+            match verdict {
+                FzilAddVerdict::Added { id } => {
+                    sync.imported_ids.insert(CorpusId::from(id as usize));
+                    report.imported += 1;
+                }
+                FzilAddVerdict::Buffered => report.imported += 1,
+                FzilAddVerdict::Duplicate => report.duplicates += 1,
+                FzilAddVerdict::Rejected { .. } => report.rejected += 1,
+                FzilAddVerdict::Dropped => {}
+            }
+        }
+        Ok(report)
+    }
 
-        let testcase = state.corpus().get(next_id).unwrap();  // Get the testcase
-        let testcase_borrowed = testcase.borrow();  // Borrow the testcase
-        let input = testcase_borrowed.input().as_ref().unwrap();
-        input.bytes().to_vec()  // Return as Vec<u8>
+    // The Rejected verdict for inputs that fail program validation, None if they pass or
+    // validation is off
+    fn validate(&self, bytes: &[u8]) -> Option<FzilAddVerdict> {
+        if !*self.validate_programs.lock().unwrap() {
+            return None;
+        }
+        let reason = fuzzil::decode_program(bytes).err()?;
+        self.stats.lock().unwrap().record_invalid();
+        Some(FzilAddVerdict::Rejected { reason })
+    }
+
+    fn on_added(&self, id: CorpusId, input: &PendingInput) {
+        let len = input.bytes.len();
+        if let Some(exec_time) = input.exec_time {
+            self.exec_times.lock().unwrap().insert(id, exec_time);
+        }
+        self.change_log.lock().unwrap().added(id);
+        self.stats.lock().unwrap().record_add();
+        self.log_event(FzilEventKind::Add, id, len);
+        let id_usize: usize = id.into();
+        self.notify(Notification::CorpusAdd { id: id_usize as u64, len: len as u64 });
+    }
+
+    // Raised when corpus writes start failing (the first input is buffered) and once the
+    // buffer has been flushed
+    fn on_write_health(&self, failing: bool, pending: usize) {
+        let event = if failing { FzilEventKind::WriteFailed } else { FzilEventKind::WriteRecovered };
+        if let Some(log) = self.event_log.lock().unwrap().as_mut() {
+            log.record_health(event, pending);
+        }
+        self.notify(Notification::WriteHealth {
+            failing,
+            pending: pending as u64,
+        });
+    }
+
+    fn notify(&self, notification: Notification) {
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener.notify(notification);
+        }
+    }
+
+    // Returns the snapshot size in bytes
+    fn write_snapshot(&self, path: &str) -> Result<usize, FuzzilliBridgeError> {
+        let mut state = self.state.write().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        self.flush_pending_locked(&mut state, &mut pending, &mut self.seen.lock().unwrap());
+        if !pending.is_empty() {
+            println!("Saving state with {} inputs still waiting to be written", pending.len());
+        }
+        let serialized = postcard::to_allocvec(&*state).map_err(|e| FuzzilliBridgeError::InvalidSnapshot {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })?;
+        write_file_atomic(path, &serialized)?;
+        Ok(serialized.len())
+    }
+
+    fn log_event(&self, event: FzilEventKind, id: CorpusId, len: usize) {
+        if let Some(log) = self.event_log.lock().unwrap().as_mut() {
+            log.record(event, id, len);
+        }
     }
 }
 
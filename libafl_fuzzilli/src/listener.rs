@@ -0,0 +1,50 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+// Implemented on the host side to be told about corpus activity instead of polling for it
+#[uniffi::export(callback_interface)]
+pub trait FzilListener: Send + Sync {
+    fn on_corpus_add(&self, id: u64, len: u64);
+    // `id` refers to the solutions corpus
+    fn on_solution(&self, id: u64, signal: i32);
+    // `failing` is true once corpus writes start failing and false once they recovered;
+    // `pending` is how many inputs are waiting in the overflow buffer
+    fn on_write_health(&self, failing: bool, pending: u64);
+}
+
+#[derive(Debug)]
+pub(crate) enum Notification {
+    CorpusAdd { id: u64, len: u64 },
+    Solution { id: u64, signal: i32 },
+    WriteHealth { failing: bool, pending: u64 },
+}
+
+// Calls into the host happen on a dedicated thread, so they never run while the bridge holds
+// a lock and a slow or re-entrant listener cannot stall the fuzzer. Notifications arrive in
+// the order they were sent.
+#[derive(Debug)]
+pub(crate) struct ListenerHandle {
+    sender: Sender<Notification>,
+}
+
+impl ListenerHandle {
+    pub(crate) fn spawn(listener: Box<dyn FzilListener>) -> ListenerHandle {
+        let (sender, receiver) = mpsc::channel();
+        // Ends once the handle (and with it the sender) is dropped
+        thread::spawn(move || {
+            for notification in receiver {
+                match notification {
+                    Notification::CorpusAdd { id, len } => listener.on_corpus_add(id, len),
+                    Notification::Solution { id, signal } => listener.on_solution(id, signal),
+                    Notification::WriteHealth { failing, pending } => listener.on_write_health(failing, pending),
+                }
+            }
+        });
+        ListenerHandle { sender }
+    }
+
+    pub(crate) fn notify(&self, notification: Notification) {
+        // The thread only goes away with the handle, so this cannot fail while we hold it
+        let _ = self.sender.send(notification);
+    }
+}
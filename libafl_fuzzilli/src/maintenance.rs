@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::{FzilStats, FzilSyncReport};
+
+// Outcome of one maintenance pass
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct FzilMaintenanceReport {
+    // Inputs still held in memory after retrying their disk writes
+    pub pending_writes: u64,
+    // None when syncing is off
+    pub sync: Option<FzilSyncReport>,
+    // Whether a checkpoint was written
+    pub checkpointed: bool,
+    // Stats after the pass; also written to `<checkpoint>.stats.json` when checkpointing
+    pub stats: FzilStats,
+}
+
+// Periodic housekeeping run from next_input, so hosts don't have to call
+// flush_pending/sync_now/save_state/get_stats themselves
+#[derive(Debug)]
+pub(crate) struct MaintenanceWindow {
+    interval: Duration,
+    last_run: Instant,
+    pub(crate) checkpoint_path: Option<PathBuf>,
+}
+
+impl MaintenanceWindow {
+    pub(crate) fn new(interval: Duration, checkpoint_path: Option<PathBuf>) -> MaintenanceWindow {
+        // The first pass happens one interval after enabling, not right away
+        MaintenanceWindow {
+            interval,
+            last_run: Instant::now(),
+            checkpoint_path,
+        }
+    }
+
+    pub(crate) fn is_due(&self) -> bool {
+        !self.interval.is_zero() && self.last_run.elapsed() >= self.interval
+    }
+
+    pub(crate) fn mark_run(&mut self) {
+        self.last_run = Instant::now();
+    }
+}
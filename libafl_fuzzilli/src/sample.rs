@@ -0,0 +1,186 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Duration;
+
+use libafl::{
+    corpus::{Corpus, CorpusId},
+    inputs::BytesInput,
+};
+use rand::Rng;
+
+fn to_u64(id: CorpusId) -> u64 {
+    let id_usize: usize = id.into();
+    id_usize as u64
+}
+
+// Quicker programs weigh more, like LibAFL's time-aware schedulers favour them
+fn weight(exec_time: Duration) -> f64 {
+    1.0 / exec_time.as_micros().max(1) as f64
+}
+
+// A sampling key, ordered by value (never NaN)
+struct Key(f64, CorpusId);
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// Up to `n` ids of enabled entries picked at random without replacement, in one pass over
+// the ids and in O(n) memory; the inputs are never loaded. With `exec_times`, an entry's
+// chance is proportional to 1 / exec time, and entries without one weigh as much as the
+// average timed entry. If `exec_times` is None or empty, the pick is uniform.
+pub(crate) fn sample_ids<C: Corpus<Input = BytesInput>>(
+    corpus: &C,
+    n: usize,
+    exec_times: Option<&HashMap<CorpusId, Duration>>,
+) -> Vec<u64> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let ids = core::iter::successors(corpus.first(), |id| corpus.next(*id));
+    let mut rng = rand::thread_rng();
+
+    if let Some(exec_times) = exec_times.filter(|exec_times| !exec_times.is_empty()) {
+        let average = exec_times.values().map(|t| weight(*t)).sum::<f64>() / exec_times.len() as f64;
+        // Efraimidis-Spirakis: keep the n largest keys u^(1/w), compared as ln(u)/w
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for id in ids {
+            let w = exec_times.get(&id).map_or(average, |t| weight(*t));
+            let u: f64 = rng.gen_range(f64::MIN_POSITIVE..1.0);
+            heap.push(Reverse(Key(u.ln() / w, id)));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+        return heap.into_iter().map(|Reverse(Key(_, id))| to_u64(id)).collect();
+    }
+
+    let mut reservoir = Vec::with_capacity(n.min(corpus.count()));
+    for (seen, id) in ids.enumerate() {
+        if reservoir.len() < n {
+            reservoir.push(to_u64(id));
+        } else {
+            let slot = rng.gen_range(0..=seen);
+            if slot < n {
+                reservoir[slot] = to_u64(id);
+            }
+        }
+    }
+    reservoir
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use libafl::corpus::{InMemoryCorpus, OnDiskCorpus, Testcase};
+
+    use super::*;
+
+    fn corpus(n: usize) -> InMemoryCorpus<BytesInput> {
+        let mut corpus = InMemoryCorpus::new();
+        for i in 0..n {
+            corpus.add(Testcase::new(BytesInput::new(vec![i as u8]))).unwrap();
+        }
+        corpus
+    }
+
+    fn distinct(ids: &[u64]) -> usize {
+        ids.iter().collect::<HashSet<_>>().len()
+    }
+
+    #[test]
+    fn uniform_picks_distinct_ids() {
+        let corpus = corpus(10);
+        assert!(sample_ids(&corpus, 0, None).is_empty());
+        let sample = sample_ids(&corpus, 4, None);
+        assert_eq!((sample.len(), distinct(&sample)), (4, 4));
+        assert!(sample.iter().all(|id| *id < 10));
+        // Asking for more than there is returns everything
+        let mut all = sample_ids(&corpus, 50, None);
+        all.sort();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn uniform_skips_removed_ids() {
+        let mut corpus = corpus(5);
+        corpus.remove(CorpusId::from(2usize)).unwrap();
+        for _ in 0..50 {
+            assert!(!sample_ids(&corpus, 2, None).contains(&2));
+        }
+    }
+
+    #[test]
+    fn weighted_picks_distinct_ids() {
+        let corpus = corpus(10);
+        let exec_times = HashMap::from([(CorpusId::from(3usize), Duration::from_micros(5))]);
+        assert!(sample_ids(&corpus, 0, Some(&exec_times)).is_empty());
+        let sample = sample_ids(&corpus, 6, Some(&exec_times));
+        assert_eq!((sample.len(), distinct(&sample)), (6, 6));
+        assert_eq!(sample_ids(&corpus, 50, Some(&exec_times)).len(), 10);
+    }
+
+    #[test]
+    fn weighted_favours_quick_entries() {
+        let corpus = corpus(3);
+        // Weights 1/10, 1/1000, and the average of both for the untimed entry 2
+        let exec_times = HashMap::from([
+            (CorpusId::from(0usize), Duration::from_micros(10)),
+            (CorpusId::from(1usize), Duration::from_micros(1000)),
+        ]);
+        let mut picks = [0; 3];
+        for _ in 0..3000 {
+            for id in sample_ids(&corpus, 1, Some(&exec_times)) {
+                picks[id as usize] += 1;
+            }
+        }
+        // Expected about 66%, 0.7% and 33%
+        assert!(picks[0] > 1700, "{:?}", picks);
+        assert!(picks[1] < 100, "{:?}", picks);
+        assert!(picks[2] > 700, "{:?}", picks);
+    }
+
+    #[test]
+    fn weighted_without_exec_times_is_uniform() {
+        let corpus = corpus(2);
+        let mut picks = [0; 2];
+        for _ in 0..2000 {
+            for id in sample_ids(&corpus, 1, Some(&HashMap::new())) {
+                picks[id as usize] += 1;
+            }
+        }
+        assert!(picks.iter().all(|count| *count > 800), "{:?}", picks);
+    }
+
+    #[test]
+    fn never_loads_inputs() {
+        let dir = std::env::temp_dir().join(format!("fzil_sample_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut corpus = OnDiskCorpus::<BytesInput>::new(&dir).unwrap();
+        for i in 0..4u8 {
+            corpus.add(Testcase::new(BytesInput::new(vec![i]))).unwrap();
+        }
+        // With the files gone, loading any input would fail
+        std::fs::remove_dir_all(&dir).unwrap();
+        let exec_times = HashMap::from([(CorpusId::from(1usize), Duration::from_micros(1))]);
+        assert_eq!(sample_ids(&corpus, 3, None).len(), 3);
+        assert_eq!(sample_ids(&corpus, 3, Some(&exec_times)).len(), 3);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use libafl::corpus::CorpusId;
+use serde::Serialize;
+
+// How far back adds are counted for `adds_last_minute`
+const ADD_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Snapshot of what the bridge has seen, for display on the host side
+#[derive(uniffi::Record, Serialize, Debug, Clone)]
+pub struct FzilStats {
+    pub corpus_size: u64,
+    pub solutions_count: u64,
+    pub pending_writes: u64,
+    pub total_adds: u64,
+    pub duplicates_rejected: u64,
+    // Inputs turned away by program validation
+    pub invalid_rejected: u64,
+    pub adds_last_minute: u64,
+    pub total_suggestions: u64,
+    // Pick distribution: how many distinct entries were ever scheduled, and the busiest one
+    pub distinct_scheduled: u64,
+    pub most_scheduled_id: Option<u64>,
+    pub most_scheduled_count: u64,
+    // Milliseconds since the epoch of the last entry added to the corpus
+    pub last_add_ts_ms: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StatsTracker {
+    total_adds: u64,
+    duplicates_rejected: u64,
+    invalid_rejected: u64,
+    recent_adds: VecDeque<Instant>,
+    total_suggestions: u64,
+    picks: HashMap<CorpusId, u64>,
+    last_add_ts_ms: Option<u64>,
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl StatsTracker {
+    pub(crate) fn record_add(&mut self) {
+        let now = Instant::now();
+        self.total_adds += 1;
+        self.recent_adds.push_back(now);
+        self.expire_adds(now);
+        self.last_add_ts_ms = Some(now_ms());
+    }
+
+    pub(crate) fn record_duplicate(&mut self) {
+        self.duplicates_rejected += 1;
+    }
+
+    pub(crate) fn record_invalid(&mut self) {
+        self.invalid_rejected += 1;
+    }
+
+    pub(crate) fn record_suggestion(&mut self, id: CorpusId) {
+        self.total_suggestions += 1;
+        *self.picks.entry(id).or_insert(0) += 1;
+    }
+
+    fn expire_adds(&mut self, now: Instant) {
+        while self
+            .recent_adds
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > ADD_RATE_WINDOW)
+        {
+            self.recent_adds.pop_front();
+        }
+    }
+
+    // Fills in everything tracked here; the corpus-derived counts are left at zero for the caller
+    pub(crate) fn snapshot(&mut self) -> FzilStats {
+        self.expire_adds(Instant::now());
+        let busiest = self
+            .picks
+            .iter()
+            .max_by_key(|(id, count)| (**count, core::cmp::Reverse(**id)));
+        FzilStats {
+            corpus_size: 0,
+            solutions_count: 0,
+            pending_writes: 0,
+            total_adds: self.total_adds,
+            duplicates_rejected: self.duplicates_rejected,
+            invalid_rejected: self.invalid_rejected,
+            adds_last_minute: self.recent_adds.len() as u64,
+            total_suggestions: self.total_suggestions,
+            distinct_scheduled: self.picks.len() as u64,
+            most_scheduled_id: busiest.map(|(id, _)| {
+                let id_usize: usize = (*id).into();
+                id_usize as u64
+            }),
+            most_scheduled_count: busiest.map_or(0, |(_, count)| *count),
+            last_add_ts_ms: self.last_add_ts_ms,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use libafl::corpus::CorpusId;
+use libafl_bolts::fs::write_file_atomic;
+
+use crate::dedup::SeenInputs;
+use crate::FuzzilliBridgeError;
+
+// Outcome of one exchange with the sync directory
+#[derive(uniffi::Record, Debug, Clone, Default)]
+pub struct FzilSyncReport {
+    // Local entries written to our own queue
+    pub exported: u64,
+    // Sibling entries added to the local corpus
+    pub imported: u64,
+    // Sibling entries skipped because the local corpus already had them
+    pub duplicates: u64,
+    // Sibling entries that failed program validation
+    pub rejected: u64,
+}
+
+// AFL-style corpus exchange through a shared directory: every instance writes its entries to
+// `<sync_dir>/<instance>/queue` and picks up new files from its siblings' queues
+#[derive(Debug)]
+pub(crate) struct SyncDir {
+    root: PathBuf,
+    instance: String,
+    interval: Duration,
+    last_sync: Option<Instant>,
+    // Position in the scheduler's added log up to which entries were exported
+    pub(crate) exported_version: usize,
+    // Entries that came from siblings, never exported back
+    pub(crate) imported_ids: HashSet<CorpusId>,
+    // Sibling files already looked at
+    seen_files: HashSet<PathBuf>,
+}
+
+impl SyncDir {
+    pub(crate) fn new(root: &str, instance: &str, interval: Duration) -> Result<SyncDir, FuzzilliBridgeError> {
+        let sync = SyncDir {
+            root: PathBuf::from(root),
+            instance: instance.to_string(),
+            interval,
+            last_sync: None,
+            exported_version: 0,
+            imported_ids: HashSet::new(),
+            seen_files: HashSet::new(),
+        };
+        std::fs::create_dir_all(sync.queue_dir())?;
+        Ok(sync)
+    }
+
+    fn queue_dir(&self) -> PathBuf {
+        self.root.join(&self.instance).join("queue")
+    }
+
+    pub(crate) fn is_due(&self) -> bool {
+        !self.interval.is_zero() && self.last_sync.is_none_or(|t| t.elapsed() >= self.interval)
+    }
+
+    pub(crate) fn mark_synced(&mut self) {
+        self.last_sync = Some(Instant::now());
+    }
+
+    // Queue files are named after their content: corpus ids restart at 0 every session, so
+    // id-based names from an earlier run would collide with new entries. Exporting the same
+    // input again just rewrites the same file.
+    pub(crate) fn export(&self, bytes: &[u8]) -> Result<(), FuzzilliBridgeError> {
+        let path = self.queue_dir().join(format!("{:016x}", SeenInputs::hash(bytes)));
+        write_file_atomic(path, bytes)?;
+        Ok(())
+    }
+
+    // Files in sibling queues that have not been looked at yet. Dotfiles are skipped, they
+    // are in-progress atomic writes.
+    pub(crate) fn new_sibling_files(&mut self) -> Result<Vec<PathBuf>, FuzzilliBridgeError> {
+        let mut files = Vec::new();
+        for sibling in std::fs::read_dir(&self.root)? {
+            let sibling = sibling?;
+            if sibling.file_name().to_string_lossy() == self.instance {
+                continue;
+            }
+            let queue = sibling.path().join("queue");
+            if !queue.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&queue)? {
+                let path = entry?.path();
+                if is_hidden(&path) || !path.is_file() || self.seen_files.contains(&path) {
+                    continue;
+                }
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    pub(crate) fn mark_seen(&mut self, path: PathBuf) {
+        self.seen_files.insert(path);
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
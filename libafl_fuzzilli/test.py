@@ -0,0 +1,27 @@
+import libafl_fuzzilli as lf
+
+
+def test_fzil_scheduler():
+    # Keep the corpus in memory, handy for experiments in a notebook
+    scheduler = lf.MyFzilScheduler.with_backend(lf.FzilCorpusBackend.IN_MEMORY())
+    print("Scheduler created successfully")
+
+    # Add a batch of inputs, one verdict per input
+    verdicts = scheduler.add_inputs([b"Hello, Fuzzing!", b"Another test case", b"Hello, Fuzzing!"])
+    print(f"Verdicts: {verdicts}")
+
+    # Ask for the next input together with its corpus id
+    suggestion = scheduler.suggest_next()
+    print(f"Suggested entry {suggestion.id}: {suggestion.bytes}")
+
+    # Fetch the next input from the scheduler as bytes
+    print(f"Next Input: {scheduler.next_input()}")
+
+    print(scheduler.get_stats())
+
+
+# Call the test function
+try:
+    test_fzil_scheduler()
+except lf.FuzzilliBridgeError as e:
+    print(f"Bridge error: {e!r}")
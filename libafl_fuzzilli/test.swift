@@ -1,9 +1,9 @@
 import fs
 import Foundation
 
-func testFzilScheduler() {
+func testFzilScheduler() throws {
     // Create a new scheduler
-    let scheduler = MyFzilScheduler() // Direct initialization
+    let scheduler = try MyFzilScheduler() // Direct initialization
     print("Scheduler created successfully")
 
     // Test adding an input using Data
@@ -12,7 +12,7 @@ func testFzilScheduler() {
     print("Added input: \(testInput)")
 
     // Fetch and print the current test case as Data
-    let currentTestcaseData = scheduler.nextInput()
+    let currentTestcaseData = try scheduler.nextInput()
     print("Current Testcase: \(String(data: currentTestcaseData, encoding: .utf8) ?? "Invalid Data")")
 
     // Add another input and get the next input from the scheduler
@@ -21,10 +21,14 @@ func testFzilScheduler() {
     print("Added input: \(anotherInput)")
 
     // Fetch and print the next input from the scheduler as Data
-    let nextInputData = scheduler.nextInput()
+    let nextInputData = try scheduler.nextInput()
     print("Next Input: \(String(data: nextInputData, encoding: .utf8) ?? "Invalid Data")")
 }
 
 // Call the test function
-testFzilScheduler()
+do {
+    try testFzilScheduler()
+} catch {
+    print("Bridge error: \(error)")
+}
 